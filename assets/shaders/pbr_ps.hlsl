@@ -18,10 +18,9 @@ struct Material {
 [[vk::binding(32, 0)]] SamplerState base_sampler;
 [[vk::binding(0, 1)]] Texture2D<float4> base;
 [[vk::binding(1, 1)]] Texture2D<float4> normals;
-[[vk::binding(2, 1)]] Texture2D<float4> metallic_roughness;
-[[vk::binding(3, 1)]] Texture2D<float4> occlusion;
-[[vk::binding(4, 1)]] Texture2D<float4> emissive;
-[[vk::binding(5, 1)]] ConstantBuffer<Material> material;
+[[vk::binding(2, 1)]] Texture2D<float4> occlusion_roughness_metallic;
+[[vk::binding(3, 1)]] Texture2D<float4> emissive;
+[[vk::binding(4, 1)]] ConstantBuffer<Material> material;
 
 float3 unpack_normal(float4 tx) {
     float2 normal_xy = tx.xy * 2.0 - 1.0;
@@ -31,10 +30,10 @@ float3 unpack_normal(float4 tx) {
 
 float4 main(VsOut psin) : SV_TARGET {
     float3 albedo = base.Sample(base_sampler, psin.uv1).rgb;
-    float3 mr = metallic_roughness.Sample(base_sampler, psin.uv1).rgb;
-    float ao = occlusion.Sample(base_sampler, psin.uv1).r;
-    float metallic = mr.b;
-    float roughness = mr.g;
+    float3 orm = occlusion_roughness_metallic.Sample(base_sampler, psin.uv1).rgb;
+    float ao = orm.r;
+    float roughness = orm.g;
+    float metallic = orm.b;
     float3 normal = unpack_normal(normals.Sample(base_sampler, psin.uv1));
 
     roughness = max(roughness, 0.0001);
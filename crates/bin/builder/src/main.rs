@@ -1,5 +1,6 @@
 use std::{
-    fs, io,
+    fs::{self, File},
+    io,
     path::Path,
     sync::{atomic::AtomicBool, Arc},
     thread,
@@ -8,20 +9,28 @@ use std::{
 
 use bevy_tasks::{AsyncComputeTaskPool, TaskPool};
 use clap::{Arg, ArgAction};
-use dess_asset_pipeline::{ContentProcessor, ImportContext};
-use dess_assets::{GltfSource, ShaderSource, ROOT_DATA_PATH};
-use log::info;
+use dess_asset_pipeline::{write_ktx2, ContentProcessor, Error, ImportContext, ImportSettings};
+use dess_assets::{
+    get_cached_asset_path, AssetLoad, AssetRef, AtlasSource, GltfSceneSource, GltfSource,
+    ImageAsset, ImageCompression, ShaderSource, ROOT_DATA_PATH,
+};
+use log::{info, warn};
 use notify::{RecursiveMode, Watcher};
 
 fn collect(processor: &ContentProcessor, root: &Path) -> io::Result<()> {
     for path in fs::read_dir(root)? {
         let path = path?;
-        if path.path().is_dir() {
+        if path.path().is_dir() && path.path().extension().is_some_and(|x| x == "atlas") {
+            let path = path.path().strip_prefix(ROOT_DATA_PATH).unwrap().to_owned();
+            let path_str = path.to_str().unwrap().replace('\\', "/");
+            processor.import(Box::new(AtlasSource::from_directory(path_str)));
+        } else if path.path().is_dir() {
             collect(processor, &path.path())?
         } else {
             let path = path.path().strip_prefix(ROOT_DATA_PATH).unwrap().to_owned();
             let path_str = path.to_str().unwrap().replace('\\', "/");
-            if path_str.ends_with(".gltf") {
+            if path_str.ends_with(".gltf") || path_str.ends_with(".glb") {
+                processor.import(Box::new(GltfSceneSource::new(&path_str)));
                 processor.import(Box::new(GltfSource::new(path_str)));
             } else if path_str.ends_with("_ps.hlsl") {
                 processor.import(Box::new(ShaderSource::fragment(path_str)));
@@ -36,6 +45,20 @@ fn collect(processor: &ContentProcessor, root: &Path) -> io::Result<()> {
     Ok(())
 }
 
+fn print_reports(processor: &ContentProcessor) {
+    for (mesh, report) in processor.mesh_reports() {
+        warn!("Mesh {}: {}", mesh, report);
+    }
+}
+
+fn export_ktx2(asset: &str, output: &str) -> Result<(), Error> {
+    let asset = asset
+        .parse::<AssetRef>()
+        .map_err(|err| Error::ProcessingFailed(err.to_string()))?;
+    let image = ImageAsset::from_bytes(&fs::read(get_cached_asset_path(asset))?)?;
+    write_ktx2(&image, &mut File::create(output)?)
+}
+
 fn main() {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
     let args = clap::Command::new("builder")
@@ -48,11 +71,44 @@ fn main() {
                 .required(false)
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("compression")
+                .long("compression")
+                .help("Default texture compression preset")
+                .value_parser(["none", "fast", "basic", "slow", "bc1", "bc3"])
+                .default_value("basic"),
+        )
+        .subcommand(
+            clap::Command::new("export")
+                .about("Export cooked texture to KTX2")
+                .arg(Arg::new("asset").required(true).help("Cooked asset UUID"))
+                .arg(Arg::new("output").required(true).help("Output KTX2 file")),
+        )
         .get_matches();
+    if let Some(("export", args)) = args.subcommand() {
+        let asset = args.get_one::<String>("asset").unwrap();
+        let output = args.get_one::<String>("output").unwrap();
+        if let Err(err) = export_ktx2(asset, output) {
+            eprintln!("Failed to export {}: {:?}", asset, err);
+            std::process::exit(1);
+        }
+        return;
+    }
+    let settings = ImportSettings {
+        compression: match args.get_one::<String>("compression").unwrap().as_str() {
+            "none" => ImageCompression::Uncompressed,
+            "fast" => ImageCompression::Fast,
+            "slow" => ImageCompression::Slow,
+            "bc1" => ImageCompression::Bc1,
+            "bc3" => ImageCompression::Bc3,
+            _ => ImageCompression::Basic,
+        },
+    };
     AsyncComputeTaskPool::get_or_init(TaskPool::new);
-    let processor = ContentProcessor::default();
+    let processor = ContentProcessor::new(settings);
     collect(&processor, Path::new(ROOT_DATA_PATH)).unwrap();
     processor.process();
+    print_reports(&processor);
     let need_reimport = Arc::new(AtomicBool::new(false));
 
     if args.get_flag("watch") {
@@ -68,9 +124,10 @@ fn main() {
                 .unwrap();
             thread::sleep(Duration::from_secs(1));
             if need_reimport.load(std::sync::atomic::Ordering::Acquire) {
-                let processor = ContentProcessor::default();
+                let processor = ContentProcessor::new(settings);
                 collect(&processor, Path::new(ROOT_DATA_PATH)).unwrap();
                 processor.process();
+                print_reports(&processor);
                 need_reimport.store(false, std::sync::atomic::Ordering::Release);
             }
         }
@@ -0,0 +1,167 @@
+use dess_assets::AnimationInterpolation;
+
+fn max_difference(a: &[f32], b: &[f32]) -> f32 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0, f32::max)
+}
+
+pub(crate) fn lerp(a: &[f32], b: &[f32], t: f32) -> Vec<f32> {
+    a.iter().zip(b).map(|(a, b)| a + (b - a) * t).collect()
+}
+
+pub(crate) fn slerp(a: &[f32], b: &[f32], t: f32) -> Vec<f32> {
+    let a = glam::Quat::from_slice(a);
+    let b = glam::Quat::from_slice(b);
+
+    a.slerp(b, t).to_array().to_vec()
+}
+
+/// Quaternion and its negation are the same rotation.
+pub(crate) fn rotation_difference(a: &[f32], b: &[f32]) -> f32 {
+    let negated = b.iter().map(|x| -x).collect::<Vec<_>>();
+
+    max_difference(a, b).min(max_difference(a, &negated))
+}
+
+pub(crate) fn value_difference(a: &[f32], b: &[f32]) -> f32 {
+    max_difference(a, b)
+}
+
+/// Removes keys that can be restored by interpolation of neighbours with
+/// error below tolerance. Values have `stride` components per key. Returns
+/// indices of kept keys.
+pub(crate) fn reduce_keys(
+    times: &[f32],
+    values: &[f32],
+    stride: usize,
+    interpolation: AnimationInterpolation,
+    tolerance: f32,
+    interpolate: impl Fn(&[f32], &[f32], f32) -> Vec<f32>,
+    difference: impl Fn(&[f32], &[f32]) -> f32,
+) -> Vec<usize> {
+    let value = |index: usize| &values[index * stride..(index + 1) * stride];
+    if times.len() < 3 || interpolation == AnimationInterpolation::CubicSpline {
+        return (0..times.len()).collect();
+    }
+    let mut kept = vec![0];
+    for index in 1..times.len() - 1 {
+        let last = *kept.last().unwrap();
+        let removable = match interpolation {
+            AnimationInterpolation::Step => difference(value(last), value(index)) <= tolerance,
+            _ => {
+                let next = index + 1;
+                let duration = times[next] - times[last];
+                (last + 1..next).all(|key| {
+                    let t = if duration > 0.0 {
+                        (times[key] - times[last]) / duration
+                    } else {
+                        0.0
+                    };
+                    let restored = interpolate(value(last), value(next), t);
+                    difference(&restored, value(key)) <= tolerance
+                })
+            }
+        };
+        if !removable {
+            kept.push(index);
+        }
+    }
+    kept.push(times.len() - 1);
+
+    kept
+}
+
+/// Copies values of kept keys. Cubic spline keys have in tangent, value and
+/// out tangent, `stride` components each.
+pub(crate) fn kept_values(
+    values: &[f32],
+    kept: &[usize],
+    stride: usize,
+    interpolation: AnimationInterpolation,
+) -> Vec<f32> {
+    let key_stride = if interpolation == AnimationInterpolation::CubicSpline {
+        stride * 3
+    } else {
+        stride
+    };
+
+    kept.iter()
+        .flat_map(|x| values[x * key_stride..(x + 1) * key_stride].iter().copied())
+        .collect()
+}
+
+/// Quantizes quaternions to signed 16 bit values scaled by range.
+pub(crate) fn quantize_rotations(values: &[[f32; 4]]) -> (f32, Vec<[i16; 4]>) {
+    let range = values.iter().flatten().map(|x| x.abs()).fold(1.0, f32::max);
+    let values = values
+        .iter()
+        .map(|x| x.map(|x| ((x / range).clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16))
+        .collect();
+
+    (range, values)
+}
+
+#[cfg(test)]
+mod test {
+    use dess_assets::AnimationInterpolation;
+
+    use super::{kept_values, lerp, reduce_keys, value_difference};
+
+    #[test]
+    fn reduce_linear_keys() {
+        let times = [0.0, 1.0, 2.0, 3.0, 4.0];
+        let values = [0.0, 1.0, 2.0, 5.0, 8.0];
+        let kept = reduce_keys(
+            &times,
+            &values,
+            1,
+            AnimationInterpolation::Linear,
+            0.001,
+            lerp,
+            value_difference,
+        );
+        assert_eq!(vec![0, 2, 4], kept);
+    }
+
+    #[test]
+    fn reduce_step_keys() {
+        let times = [0.0, 1.0, 2.0, 3.0, 4.0];
+        let values = [0.0, 0.0, 1.0, 1.0, 1.0];
+        let kept = reduce_keys(
+            &times,
+            &values,
+            1,
+            AnimationInterpolation::Step,
+            0.001,
+            lerp,
+            value_difference,
+        );
+        assert_eq!(vec![0, 2, 4], kept);
+    }
+
+    #[test]
+    fn cubic_spline_keys_are_kept_whole() {
+        let times = [0.0, 1.0, 2.0];
+        // In tangent, value, out tangent for each of 3 keys, 2 components each
+        let values = (0..18).map(|x| x as f32).collect::<Vec<_>>();
+        let kept = reduce_keys(
+            &times,
+            &values,
+            2,
+            AnimationInterpolation::CubicSpline,
+            0.001,
+            lerp,
+            value_difference,
+        );
+        assert_eq!(
+            values,
+            kept_values(&values, &kept, 2, AnimationInterpolation::CubicSpline)
+        );
+        assert_eq!(
+            vec![0.0, 1.0, 4.0, 5.0],
+            kept_values(&values[..6], &[0, 2], 2, AnimationInterpolation::Linear)
+        );
+    }
+}
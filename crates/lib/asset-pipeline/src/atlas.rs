@@ -0,0 +1,362 @@
+use std::{collections::HashMap, fs, path::Path, sync::Arc};
+
+use dess_assets::{
+    get_absolute_asset_path, Asset, AtlasAsset, AtlasInput, AtlasSource, AtlasSprite,
+    ImageDataSource, ImageRgba8Data,
+};
+
+use crate::{
+    is_asset_changed, load_rgba, process_rgba, AssetImporter, Error, ImportContext, ImportSettings,
+};
+
+const MAX_ATLAS_SIZE: u32 = 16384;
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "tga", "bmp", "tif", "tiff", "webp", "pnm", "hdr",
+];
+
+/// Skyline bottom-left rectangle packer.
+struct SkylinePacker {
+    width: u32,
+    height: u32,
+    // x, y, width
+    skyline: Vec<(u32, u32, u32)>,
+}
+
+impl SkylinePacker {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            skyline: vec![(0, 0, width)],
+        }
+    }
+
+    fn insert(&mut self, width: u32, height: u32) -> Option<[u32; 2]> {
+        let mut best: Option<(usize, u32, u32)> = None;
+        for index in 0..self.skyline.len() {
+            let Some(y) = self.fit(index, width, height) else {
+                continue;
+            };
+            match best {
+                Some((_, _, best_y)) if best_y <= y => {}
+                _ => best = Some((index, self.skyline[index].0, y)),
+            }
+        }
+        let (index, x, y) = best?;
+        self.add_level(index, x, y + height, width);
+
+        Some([x, y])
+    }
+
+    fn fit(&self, index: usize, width: u32, height: u32) -> Option<u32> {
+        let x = self.skyline[index].0;
+        if x + width > self.width {
+            return None;
+        }
+        let mut width_left = width as i64;
+        let mut y = 0;
+        let mut index = index;
+        while width_left > 0 {
+            let (_, segment_y, segment_width) = *self.skyline.get(index)?;
+            y = y.max(segment_y);
+            if y + height > self.height {
+                return None;
+            }
+            width_left -= segment_width as i64;
+            index += 1;
+        }
+
+        Some(y)
+    }
+
+    fn add_level(&mut self, index: usize, x: u32, y: u32, width: u32) {
+        self.skyline.insert(index, (x, y, width));
+        let end = x + width;
+        let index = index + 1;
+        while index < self.skyline.len() {
+            let (segment_x, _, segment_width) = self.skyline[index];
+            if segment_x >= end {
+                break;
+            }
+            let shrink = end - segment_x;
+            if segment_width <= shrink {
+                self.skyline.remove(index);
+            } else {
+                self.skyline[index].0 += shrink;
+                self.skyline[index].2 -= shrink;
+                break;
+            }
+        }
+        let mut index = 0;
+        while index + 1 < self.skyline.len() {
+            if self.skyline[index].1 == self.skyline[index + 1].1 {
+                self.skyline[index].2 += self.skyline[index + 1].2;
+                self.skyline.remove(index + 1);
+            } else {
+                index += 1;
+            }
+        }
+    }
+}
+
+/// Packs rectangles into smallest power of two area it can find. Returns
+/// atlas size and rectangle positions in input order.
+fn pack_rects(sizes: &[[u32; 2]]) -> Option<([u32; 2], Vec<[u32; 2]>)> {
+    let mut order = (0..sizes.len()).collect::<Vec<_>>();
+    order.sort_by(|a, b| {
+        sizes[*b][1]
+            .cmp(&sizes[*a][1])
+            .then(sizes[*b][0].cmp(&sizes[*a][0]))
+    });
+    let area = sizes.iter().map(|x| x[0] as u64 * x[1] as u64).sum::<u64>();
+    let max_width = sizes.iter().map(|x| x[0]).max().unwrap_or(1);
+    let max_height = sizes.iter().map(|x| x[1]).max().unwrap_or(1);
+    let mut dimensions = [
+        max_width.next_power_of_two(),
+        max_height.next_power_of_two(),
+    ];
+    while (dimensions[0] as u64 * dimensions[1] as u64) < area {
+        grow(&mut dimensions);
+    }
+
+    while dimensions[0] <= MAX_ATLAS_SIZE && dimensions[1] <= MAX_ATLAS_SIZE {
+        let mut packer = SkylinePacker::new(dimensions[0], dimensions[1]);
+        let mut positions = vec![[0, 0]; sizes.len()];
+        let packed = order.iter().all(|index| {
+            if let Some(position) = packer.insert(sizes[*index][0], sizes[*index][1]) {
+                positions[*index] = position;
+                true
+            } else {
+                false
+            }
+        });
+        if packed {
+            return Some((dimensions, positions));
+        }
+        grow(&mut dimensions);
+    }
+
+    None
+}
+
+fn grow(dimensions: &mut [u32; 2]) {
+    if dimensions[0] <= dimensions[1] {
+        dimensions[0] <<= 1;
+    } else {
+        dimensions[1] <<= 1;
+    }
+}
+
+fn collect_files(source: &AtlasSource) -> Result<Vec<String>, Error> {
+    match &source.input {
+        AtlasInput::Files(files) => Ok(files.clone()),
+        AtlasInput::Directory(directory) => {
+            let mut files = Vec::new();
+            for entry in fs::read_dir(get_absolute_asset_path(directory)?)? {
+                let path = entry?.path();
+                let is_image = path
+                    .extension()
+                    .and_then(|x| x.to_str())
+                    .is_some_and(|x| IMAGE_EXTENSIONS.contains(&x.to_lowercase().as_str()));
+                if path.is_file() && is_image {
+                    let path = Path::new(directory).join(path.file_name().unwrap());
+                    files.push(path.to_str().unwrap().replace('\\', "/"));
+                }
+            }
+            files.sort();
+            Ok(files)
+        }
+    }
+}
+
+fn sprite_name(path: &str) -> String {
+    Path::new(path)
+        .file_stem()
+        .and_then(|x| x.to_str())
+        .unwrap_or(path)
+        .to_owned()
+}
+
+fn blit_extruded(
+    atlas: &mut [u8],
+    atlas_width: u32,
+    image: &ImageRgba8Data,
+    position: [u32; 2],
+    extrude: u32,
+) {
+    let [width, height] = image.dimensions;
+    for y in 0..height + 2 * extrude {
+        let src_y = y.saturating_sub(extrude).min(height - 1);
+        for x in 0..width + 2 * extrude {
+            let src_x = x.saturating_sub(extrude).min(width - 1);
+            let src = ((src_y * width + src_x) * 4) as usize;
+            let dst = (((position[1] + y) * atlas_width + position[0] + x) * 4) as usize;
+            atlas[dst..dst + 4].copy_from_slice(&image.data[src..src + 4]);
+        }
+    }
+}
+
+fn process_atlas(source: &AtlasSource, ctx: &dyn ImportContext) -> Result<AtlasAsset, Error> {
+    let files = collect_files(source)?;
+    let mut images = Vec::with_capacity(files.len());
+    for path in files {
+        let name = sprite_name(&path);
+        if images.iter().any(|(x, _)| *x == name) {
+            return Err(Error::ProcessingFailed(format!(
+                "Duplicate sprite {} in atlas",
+                name
+            )));
+        }
+        let image = load_rgba(&ImageDataSource::File(path))?;
+        images.push((name, image));
+    }
+    if images.is_empty() {
+        return Err(Error::ProcessingFailed("Atlas has no images".to_owned()));
+    }
+
+    let border = 2 * source.extrude + source.padding;
+    let sizes = images
+        .iter()
+        .map(|(_, image)| [image.dimensions[0] + border, image.dimensions[1] + border])
+        .collect::<Vec<_>>();
+    let (dimensions, positions) = pack_rects(&sizes).ok_or(Error::ProcessingFailed(
+        "Atlas images don't fit into maximum texture size".to_owned(),
+    ))?;
+
+    let mut data = vec![0u8; dimensions[0] as usize * dimensions[1] as usize * 4];
+    let mut sprites = HashMap::with_capacity(images.len());
+    for ((name, image), position) in images.iter().zip(positions) {
+        blit_extruded(&mut data, dimensions[0], image, position, source.extrude);
+        let x = position[0] + source.extrude;
+        let y = position[1] + source.extrude;
+        let [width, height] = image.dimensions;
+        sprites.insert(
+            name.clone(),
+            AtlasSprite {
+                rect: [x, y, width, height],
+                uv: [
+                    x as f32 / dimensions[0] as f32,
+                    y as f32 / dimensions[1] as f32,
+                    (x + width) as f32 / dimensions[0] as f32,
+                    (y + height) as f32 / dimensions[1] as f32,
+                ],
+            },
+        );
+    }
+
+    let image = ImageRgba8Data {
+        data: data.into(),
+        dimensions,
+    };
+    let compression = source
+        .desc
+        .compression
+        .unwrap_or(ctx.settings().compression);
+
+    Ok(AtlasAsset {
+        image: process_rgba(&image, source.desc, compression, atlas_mips(source.extrude))?,
+        sprites,
+    })
+}
+
+/// Mips are built for whole atlas, so texel of smallest mip must not reach
+/// past extruded border of sprite, otherwise neighbours bleed into it.
+fn atlas_mips(extrude: u32) -> usize {
+    (extrude.max(1).ilog2() + 1) as usize
+}
+
+impl AssetImporter for AtlasSource {
+    fn import(&self, ctx: &dyn ImportContext) -> Result<Arc<dyn Asset>, Error> {
+        Ok(Arc::new(process_atlas(self, ctx)?))
+    }
+
+    fn resolve(&self, settings: &ImportSettings) -> Option<Box<dyn AssetImporter>> {
+        self.desc.compression.is_none().then(|| {
+            Box::new(Self {
+                desc: self.desc.compression(settings.compression),
+                ..self.clone()
+            }) as _
+        })
+    }
+
+    fn is_changed(&self, timestamp: std::time::SystemTime) -> bool {
+        if let AtlasInput::Directory(directory) = &self.input {
+            // Adding or removing files changes directory itself.
+            if is_asset_changed(directory, timestamp) {
+                return true;
+            }
+        }
+        collect_files(self)
+            .map(|files| files.iter().any(|x| is_asset_changed(x, timestamp)))
+            .unwrap_or(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use dess_assets::{AtlasSource, ImageCompression};
+
+    use crate::{ContentProcessor, ImportContext, ImportSettings};
+
+    use super::{atlas_mips, pack_rects};
+
+    fn overlaps(a: ([u32; 2], [u32; 2]), b: ([u32; 2], [u32; 2])) -> bool {
+        a.0[0] < b.0[0] + b.1[0]
+            && b.0[0] < a.0[0] + a.1[0]
+            && a.0[1] < b.0[1] + b.1[1]
+            && b.0[1] < a.0[1] + a.1[1]
+    }
+
+    #[test]
+    fn default_compression_is_part_of_identity() {
+        let import = |compression| {
+            ContentProcessor::new(ImportSettings { compression })
+                .import(Box::new(AtlasSource::from_directory("sprites.atlas")))
+        };
+        assert_ne!(
+            import(ImageCompression::Fast),
+            import(ImageCompression::Slow)
+        );
+    }
+
+    #[test]
+    fn mips_stay_inside_extruded_border() {
+        assert_eq!(atlas_mips(0), 1);
+        assert_eq!(atlas_mips(1), 1);
+        assert_eq!(atlas_mips(2), 2);
+        assert_eq!(atlas_mips(4), 3);
+        assert_eq!(atlas_mips(7), 3);
+    }
+
+    #[test]
+    fn pack_same_size() {
+        let sizes = vec![[16, 16]; 16];
+        let (dimensions, positions) = pack_rects(&sizes).unwrap();
+        assert_eq!([64, 64], dimensions);
+        for a in 0..sizes.len() {
+            for b in a + 1..sizes.len() {
+                assert!(!overlaps(
+                    (positions[a], sizes[a]),
+                    (positions[b], sizes[b])
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn pack_mixed_sizes() {
+        let sizes = vec![[100, 20], [30, 60], [10, 10], [64, 64], [5, 90], [33, 17]];
+        let (dimensions, positions) = pack_rects(&sizes).unwrap();
+        assert!(dimensions[0].is_power_of_two() && dimensions[1].is_power_of_two());
+        for a in 0..sizes.len() {
+            assert!(positions[a][0] + sizes[a][0] <= dimensions[0]);
+            assert!(positions[a][1] + sizes[a][1] <= dimensions[1]);
+            for b in a + 1..sizes.len() {
+                assert!(!overlaps(
+                    (positions[a], sizes[a]),
+                    (positions[b], sizes[b])
+                ));
+            }
+        }
+    }
+}
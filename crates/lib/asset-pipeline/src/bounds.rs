@@ -0,0 +1,132 @@
+use dess_assets::BoundingVolume;
+
+/// Box is exact, sphere is the smaller one of box-centered sphere and Ritter's
+/// approximation.
+pub(crate) fn bounding_volume(points: &[glam::Vec3]) -> BoundingVolume {
+    let Some(first) = points.first() else {
+        return BoundingVolume::default();
+    };
+    let (min, max) = points
+        .iter()
+        .fold((*first, *first), |(min, max), x| (min.min(*x), max.max(*x)));
+    let box_center = (min + max) * 0.5;
+    let box_radius = points
+        .iter()
+        .map(|x| x.distance(box_center))
+        .fold(0.0, f32::max);
+
+    let farthest = |from: glam::Vec3| {
+        points
+            .iter()
+            .copied()
+            .max_by(|a, b| {
+                a.distance_squared(from)
+                    .total_cmp(&b.distance_squared(from))
+            })
+            .unwrap()
+    };
+    let a = farthest(*first);
+    let b = farthest(a);
+    let mut center = (a + b) * 0.5;
+    let mut radius = a.distance(b) * 0.5;
+    for point in points {
+        let distance = point.distance(center);
+        if distance > radius {
+            let new_radius = (radius + distance) * 0.5;
+            center += (*point - center) * ((new_radius - radius) / distance);
+            radius = new_radius;
+        }
+    }
+    let (center, radius) = if radius < box_radius {
+        (center, radius)
+    } else {
+        (box_center, box_radius)
+    };
+
+    BoundingVolume {
+        min: min.to_array(),
+        max: max.to_array(),
+        center: center.to_array(),
+        radius,
+    }
+}
+
+/// Encloses volumes placed with transforms.
+pub(crate) fn merge_volumes(
+    volumes: impl Iterator<Item = (BoundingVolume, glam::Mat4)> + Clone,
+) -> BoundingVolume {
+    let corners = volumes
+        .clone()
+        .flat_map(|(volume, transform)| {
+            let (min, max) = (glam::Vec3::from(volume.min), glam::Vec3::from(volume.max));
+            (0..8).map(move |corner| {
+                let mask = glam::BVec3::new(corner & 1 != 0, corner & 2 != 0, corner & 4 != 0);
+                transform.transform_point3(glam::Vec3::select(mask, max, min))
+            })
+        })
+        .collect::<Vec<_>>();
+    let Some(first) = corners.first() else {
+        return BoundingVolume::default();
+    };
+    let (min, max) = corners
+        .iter()
+        .fold((*first, *first), |(min, max), x| (min.min(*x), max.max(*x)));
+    let center = (min + max) * 0.5;
+    let radius = volumes
+        .map(|(volume, transform)| {
+            let scale = transform
+                .x_axis
+                .truncate()
+                .length()
+                .max(transform.y_axis.truncate().length())
+                .max(transform.z_axis.truncate().length());
+            transform
+                .transform_point3(volume.center.into())
+                .distance(center)
+                + volume.radius * scale
+        })
+        .fold(0.0, f32::max);
+
+    BoundingVolume {
+        min: min.to_array(),
+        max: max.to_array(),
+        center: center.to_array(),
+        radius,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{bounding_volume, merge_volumes};
+
+    #[test]
+    fn volumes_enclose_points() {
+        let points = [
+            glam::vec3(-1.0, 0.0, 0.0),
+            glam::vec3(1.0, 0.0, 0.0),
+            glam::vec3(0.0, 0.5, 0.0),
+            glam::vec3(0.0, 0.0, -0.5),
+        ];
+        let volume = bounding_volume(&points);
+        assert_eq!(volume.min, [-1.0, 0.0, -0.5]);
+        assert_eq!(volume.max, [1.0, 0.5, 0.0]);
+        assert!((volume.radius - 1.0).abs() < 1e-5);
+        for point in points {
+            assert!(point.distance(volume.center.into()) <= volume.radius + 1e-5);
+        }
+
+        let merged = merge_volumes(
+            [
+                (volume, glam::Mat4::IDENTITY),
+                (
+                    volume,
+                    glam::Mat4::from_translation(glam::vec3(10.0, 0.0, 0.0)),
+                ),
+            ]
+            .into_iter(),
+        );
+        assert_eq!(merged.min, [-1.0, 0.0, -0.5]);
+        assert_eq!(merged.max, [11.0, 0.5, 0.0]);
+        assert!(merged.radius >= 6.0);
+    }
+}
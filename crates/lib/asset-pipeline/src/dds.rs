@@ -0,0 +1,405 @@
+use ash::vk;
+use ddsfile::{Caps2, D3D10ResourceDimension, D3DFormat, Dds, DxgiFormat, FourCC, MiscFlag};
+use dess_assets::ImageAsset;
+
+use crate::Error;
+
+/// Conversion needed to turn DDS pixels into something Vulkan can sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Conversion {
+    None,
+    /// Alpha channel is garbage, fill it with 255.
+    OpaqueAlpha,
+    /// 24 bit BGR pixels, expand into BGRA.
+    ExpandBgr,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct DdsFormat {
+    format: vk::Format,
+    /// Block size in pixels, 1 for uncompressed formats.
+    block: u32,
+    /// Size of single block in bytes as stored in file.
+    bytes: u32,
+    conversion: Conversion,
+}
+
+impl DdsFormat {
+    fn new(format: vk::Format, bytes: u32) -> Self {
+        Self {
+            format,
+            block: 1,
+            bytes,
+            conversion: Conversion::None,
+        }
+    }
+
+    fn bc(format: vk::Format, bytes: u32) -> Self {
+        Self {
+            format,
+            block: 4,
+            bytes,
+            conversion: Conversion::None,
+        }
+    }
+
+    fn convert(mut self, conversion: Conversion) -> Self {
+        self.conversion = conversion;
+
+        self
+    }
+
+    fn level_size(&self, width: u32, height: u32) -> usize {
+        let width = width.max(1).div_ceil(self.block);
+        let height = height.max(1).div_ceil(self.block);
+
+        width as usize * height as usize * self.bytes as usize
+    }
+}
+
+fn pick(srgb: bool, unorm: vk::Format, srgb_format: vk::Format) -> vk::Format {
+    if srgb {
+        srgb_format
+    } else {
+        unorm
+    }
+}
+
+/// Map DXGI format. Typeless formats use `srgb` to select color space, all
+/// other formats are taken as is.
+fn dxgi_format(format: DxgiFormat, srgb: bool) -> Option<DdsFormat> {
+    let format = match format {
+        DxgiFormat::R8G8B8A8_UNorm => DdsFormat::new(vk::Format::R8G8B8A8_UNORM, 4),
+        DxgiFormat::R8G8B8A8_UNorm_sRGB => DdsFormat::new(vk::Format::R8G8B8A8_SRGB, 4),
+        DxgiFormat::R8G8B8A8_Typeless => DdsFormat::new(
+            pick(srgb, vk::Format::R8G8B8A8_UNORM, vk::Format::R8G8B8A8_SRGB),
+            4,
+        ),
+        DxgiFormat::R8G8B8A8_SNorm => DdsFormat::new(vk::Format::R8G8B8A8_SNORM, 4),
+        DxgiFormat::B8G8R8A8_UNorm => DdsFormat::new(vk::Format::B8G8R8A8_UNORM, 4),
+        DxgiFormat::B8G8R8A8_UNorm_sRGB => DdsFormat::new(vk::Format::B8G8R8A8_SRGB, 4),
+        DxgiFormat::B8G8R8A8_Typeless => DdsFormat::new(
+            pick(srgb, vk::Format::B8G8R8A8_UNORM, vk::Format::B8G8R8A8_SRGB),
+            4,
+        ),
+        DxgiFormat::B8G8R8X8_UNorm => {
+            DdsFormat::new(vk::Format::B8G8R8A8_UNORM, 4).convert(Conversion::OpaqueAlpha)
+        }
+        DxgiFormat::B8G8R8X8_UNorm_sRGB => {
+            DdsFormat::new(vk::Format::B8G8R8A8_SRGB, 4).convert(Conversion::OpaqueAlpha)
+        }
+        DxgiFormat::B8G8R8X8_Typeless => DdsFormat::new(
+            pick(srgb, vk::Format::B8G8R8A8_UNORM, vk::Format::B8G8R8A8_SRGB),
+            4,
+        )
+        .convert(Conversion::OpaqueAlpha),
+        DxgiFormat::R10G10B10A2_UNorm => DdsFormat::new(vk::Format::A2B10G10R10_UNORM_PACK32, 4),
+        DxgiFormat::R11G11B10_Float => DdsFormat::new(vk::Format::B10G11R11_UFLOAT_PACK32, 4),
+        DxgiFormat::R9G9B9E5_SharedExp => DdsFormat::new(vk::Format::E5B9G9R9_UFLOAT_PACK32, 4),
+        DxgiFormat::B5G6R5_UNorm => DdsFormat::new(vk::Format::R5G6B5_UNORM_PACK16, 2),
+        DxgiFormat::B5G5R5A1_UNorm => DdsFormat::new(vk::Format::A1R5G5B5_UNORM_PACK16, 2),
+        DxgiFormat::R16G16B16A16_Float => DdsFormat::new(vk::Format::R16G16B16A16_SFLOAT, 8),
+        DxgiFormat::R16G16B16A16_UNorm => DdsFormat::new(vk::Format::R16G16B16A16_UNORM, 8),
+        DxgiFormat::R16G16B16A16_SNorm => DdsFormat::new(vk::Format::R16G16B16A16_SNORM, 8),
+        DxgiFormat::R32G32B32A32_Float => DdsFormat::new(vk::Format::R32G32B32A32_SFLOAT, 16),
+        DxgiFormat::R16G16_Float => DdsFormat::new(vk::Format::R16G16_SFLOAT, 4),
+        DxgiFormat::R16G16_UNorm => DdsFormat::new(vk::Format::R16G16_UNORM, 4),
+        DxgiFormat::R16G16_SNorm => DdsFormat::new(vk::Format::R16G16_SNORM, 4),
+        DxgiFormat::R32G32_Float => DdsFormat::new(vk::Format::R32G32_SFLOAT, 8),
+        DxgiFormat::R8G8_UNorm => DdsFormat::new(vk::Format::R8G8_UNORM, 2),
+        DxgiFormat::R8G8_SNorm => DdsFormat::new(vk::Format::R8G8_SNORM, 2),
+        DxgiFormat::R16_Float => DdsFormat::new(vk::Format::R16_SFLOAT, 2),
+        DxgiFormat::R16_UNorm => DdsFormat::new(vk::Format::R16_UNORM, 2),
+        DxgiFormat::R16_SNorm => DdsFormat::new(vk::Format::R16_SNORM, 2),
+        DxgiFormat::R32_Float => DdsFormat::new(vk::Format::R32_SFLOAT, 4),
+        DxgiFormat::R8_UNorm | DxgiFormat::A8_UNorm => DdsFormat::new(vk::Format::R8_UNORM, 1),
+        DxgiFormat::R8_SNorm => DdsFormat::new(vk::Format::R8_SNORM, 1),
+        DxgiFormat::BC1_UNorm => DdsFormat::bc(vk::Format::BC1_RGBA_UNORM_BLOCK, 8),
+        DxgiFormat::BC1_UNorm_sRGB => DdsFormat::bc(vk::Format::BC1_RGBA_SRGB_BLOCK, 8),
+        DxgiFormat::BC1_Typeless => DdsFormat::bc(
+            pick(
+                srgb,
+                vk::Format::BC1_RGBA_UNORM_BLOCK,
+                vk::Format::BC1_RGBA_SRGB_BLOCK,
+            ),
+            8,
+        ),
+        DxgiFormat::BC2_UNorm => DdsFormat::bc(vk::Format::BC2_UNORM_BLOCK, 16),
+        DxgiFormat::BC2_UNorm_sRGB => DdsFormat::bc(vk::Format::BC2_SRGB_BLOCK, 16),
+        DxgiFormat::BC2_Typeless => DdsFormat::bc(
+            pick(
+                srgb,
+                vk::Format::BC2_UNORM_BLOCK,
+                vk::Format::BC2_SRGB_BLOCK,
+            ),
+            16,
+        ),
+        DxgiFormat::BC3_UNorm => DdsFormat::bc(vk::Format::BC3_UNORM_BLOCK, 16),
+        DxgiFormat::BC3_UNorm_sRGB => DdsFormat::bc(vk::Format::BC3_SRGB_BLOCK, 16),
+        DxgiFormat::BC3_Typeless => DdsFormat::bc(
+            pick(
+                srgb,
+                vk::Format::BC3_UNORM_BLOCK,
+                vk::Format::BC3_SRGB_BLOCK,
+            ),
+            16,
+        ),
+        DxgiFormat::BC4_UNorm | DxgiFormat::BC4_Typeless => {
+            DdsFormat::bc(vk::Format::BC4_UNORM_BLOCK, 8)
+        }
+        DxgiFormat::BC4_SNorm => DdsFormat::bc(vk::Format::BC4_SNORM_BLOCK, 8),
+        DxgiFormat::BC5_UNorm | DxgiFormat::BC5_Typeless => {
+            DdsFormat::bc(vk::Format::BC5_UNORM_BLOCK, 16)
+        }
+        DxgiFormat::BC5_SNorm => DdsFormat::bc(vk::Format::BC5_SNORM_BLOCK, 16),
+        DxgiFormat::BC6H_UF16 | DxgiFormat::BC6H_Typeless => {
+            DdsFormat::bc(vk::Format::BC6H_UFLOAT_BLOCK, 16)
+        }
+        DxgiFormat::BC6H_SF16 => DdsFormat::bc(vk::Format::BC6H_SFLOAT_BLOCK, 16),
+        DxgiFormat::BC7_UNorm => DdsFormat::bc(vk::Format::BC7_UNORM_BLOCK, 16),
+        DxgiFormat::BC7_UNorm_sRGB => DdsFormat::bc(vk::Format::BC7_SRGB_BLOCK, 16),
+        DxgiFormat::BC7_Typeless => DdsFormat::bc(
+            pick(
+                srgb,
+                vk::Format::BC7_UNORM_BLOCK,
+                vk::Format::BC7_SRGB_BLOCK,
+            ),
+            16,
+        ),
+        _ => return None,
+    };
+
+    Some(format)
+}
+
+const FOURCC_BC4U: u32 = 0x55344342;
+const FOURCC_BC4S: u32 = 0x53344342;
+const FOURCC_BC5U: u32 = 0x55354342;
+const FOURCC_BC5S: u32 = 0x53354342;
+
+/// Map legacy FourCC codes. Legacy files have no notion of color space,
+/// so `srgb` comes from image description.
+fn fourcc_format(fourcc: u32, srgb: bool) -> Option<DdsFormat> {
+    let format = match fourcc {
+        FourCC::DXT1 => DdsFormat::bc(
+            pick(
+                srgb,
+                vk::Format::BC1_RGBA_UNORM_BLOCK,
+                vk::Format::BC1_RGBA_SRGB_BLOCK,
+            ),
+            8,
+        ),
+        FourCC::DXT2 | FourCC::DXT3 => DdsFormat::bc(
+            pick(
+                srgb,
+                vk::Format::BC2_UNORM_BLOCK,
+                vk::Format::BC2_SRGB_BLOCK,
+            ),
+            16,
+        ),
+        FourCC::DXT4 | FourCC::DXT5 => DdsFormat::bc(
+            pick(
+                srgb,
+                vk::Format::BC3_UNORM_BLOCK,
+                vk::Format::BC3_SRGB_BLOCK,
+            ),
+            16,
+        ),
+        FourCC::ATI1 | FOURCC_BC4U => DdsFormat::bc(vk::Format::BC4_UNORM_BLOCK, 8),
+        FOURCC_BC4S => DdsFormat::bc(vk::Format::BC4_SNORM_BLOCK, 8),
+        FourCC::ATI2 | FOURCC_BC5U => DdsFormat::bc(vk::Format::BC5_UNORM_BLOCK, 16),
+        FOURCC_BC5S => DdsFormat::bc(vk::Format::BC5_SNORM_BLOCK, 16),
+        FourCC::A16B16G16R16 => DdsFormat::new(vk::Format::R16G16B16A16_UNORM, 8),
+        FourCC::Q16W16V16U16 => DdsFormat::new(vk::Format::R16G16B16A16_SNORM, 8),
+        FourCC::R16F => DdsFormat::new(vk::Format::R16_SFLOAT, 2),
+        FourCC::G16R16F => DdsFormat::new(vk::Format::R16G16_SFLOAT, 4),
+        FourCC::A16B16G16R16F => DdsFormat::new(vk::Format::R16G16B16A16_SFLOAT, 8),
+        FourCC::R32F => DdsFormat::new(vk::Format::R32_SFLOAT, 4),
+        FourCC::G32R32F => DdsFormat::new(vk::Format::R32G32_SFLOAT, 8),
+        FourCC::A32B32G32R32F => DdsFormat::new(vk::Format::R32G32B32A32_SFLOAT, 16),
+        _ => return None,
+    };
+
+    Some(format)
+}
+
+/// Map legacy bitmask formats.
+fn d3d_format(format: D3DFormat, srgb: bool) -> Option<DdsFormat> {
+    let rgba = pick(srgb, vk::Format::R8G8B8A8_UNORM, vk::Format::R8G8B8A8_SRGB);
+    let bgra = pick(srgb, vk::Format::B8G8R8A8_UNORM, vk::Format::B8G8R8A8_SRGB);
+    let format = match format {
+        D3DFormat::A8B8G8R8 => DdsFormat::new(rgba, 4),
+        D3DFormat::X8B8G8R8 => DdsFormat::new(rgba, 4).convert(Conversion::OpaqueAlpha),
+        D3DFormat::A8R8G8B8 => DdsFormat::new(bgra, 4),
+        D3DFormat::X8R8G8B8 => DdsFormat::new(bgra, 4).convert(Conversion::OpaqueAlpha),
+        D3DFormat::R8G8B8 => DdsFormat::new(bgra, 3).convert(Conversion::ExpandBgr),
+        D3DFormat::A2B10G10R10 => DdsFormat::new(vk::Format::A2B10G10R10_UNORM_PACK32, 4),
+        D3DFormat::A2R10G10B10 => DdsFormat::new(vk::Format::A2R10G10B10_UNORM_PACK32, 4),
+        D3DFormat::R5G6B5 => DdsFormat::new(vk::Format::R5G6B5_UNORM_PACK16, 2),
+        D3DFormat::A1R5G5B5 => DdsFormat::new(vk::Format::A1R5G5B5_UNORM_PACK16, 2),
+        D3DFormat::A4R4G4B4 => DdsFormat::new(vk::Format::A4R4G4B4_UNORM_PACK16, 2),
+        D3DFormat::G16R16 => DdsFormat::new(vk::Format::R16G16_UNORM, 4),
+        D3DFormat::A8L8 => DdsFormat::new(vk::Format::R8G8_UNORM, 2),
+        D3DFormat::L16 => DdsFormat::new(vk::Format::R16_UNORM, 2),
+        D3DFormat::L8 | D3DFormat::A8 => DdsFormat::new(vk::Format::R8_UNORM, 1),
+        _ => return None,
+    };
+
+    Some(format)
+}
+
+fn get_format(image: &Dds, srgb: bool) -> Option<DdsFormat> {
+    if let Some(header) = &image.header10 {
+        return dxgi_format(header.dxgi_format, srgb);
+    }
+    if let Some(fourcc) = &image.header.spf.fourcc {
+        return fourcc_format(fourcc.0, srgb);
+    }
+    image
+        .get_d3d_format()
+        .and_then(|format| d3d_format(format, srgb))
+}
+
+fn convert(data: &[u8], conversion: Conversion) -> Vec<u8> {
+    match conversion {
+        Conversion::None => data.to_vec(),
+        Conversion::OpaqueAlpha => {
+            let mut data = data.to_vec();
+            data.chunks_exact_mut(4).for_each(|pixel| pixel[3] = 255);
+            data
+        }
+        Conversion::ExpandBgr => data
+            .chunks_exact(3)
+            .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 255])
+            .collect(),
+    }
+}
+
+/// Returns number of layers and if image is cubemap. Every face of cubemap is
+/// a separate layer.
+fn get_layers(image: &Dds) -> (u32, bool) {
+    if let Some(header) = &image.header10 {
+        let cubemap = header.misc_flag.contains(MiscFlag::TEXTURECUBE);
+        let layers = header.array_size.max(1);
+        if cubemap {
+            (layers * 6, true)
+        } else {
+            (layers, false)
+        }
+    } else if image.header.caps2.contains(Caps2::CUBEMAP) {
+        (6, true)
+    } else {
+        (1, false)
+    }
+}
+
+pub(crate) fn process_dds(image: &Dds, srgb: bool) -> Result<ImageAsset, Error> {
+    let format = get_format(image, srgb).ok_or(Error::ProcessingFailed(
+        "Unsupported DDS pixel format".to_owned(),
+    ))?;
+    let is_volume = image.get_depth() > 1
+        || image
+            .header10
+            .as_ref()
+            .is_some_and(|x| x.resource_dimension == D3D10ResourceDimension::Texture3D);
+    if is_volume {
+        return Err(Error::ProcessingFailed(
+            "Volume DDS textures aren't supported".to_owned(),
+        ));
+    }
+    if image.header.caps2.contains(Caps2::CUBEMAP)
+        && !image.header.caps2.contains(Caps2::CUBEMAP_ALLFACES)
+    {
+        return Err(Error::ProcessingFailed(
+            "Cubemap DDS must contain all faces".to_owned(),
+        ));
+    }
+    let (layers, cubemap) = get_layers(image);
+    let levels = image.get_num_mipmap_levels().max(1);
+    let width = image.get_width();
+    let height = image.get_height();
+
+    // DDS stores every layer with its full mip chain, asset stores every mip
+    // with all layers.
+    let mut mips = vec![Vec::new(); levels as usize];
+    let mut offset = 0;
+    for _ in 0..layers {
+        for (level, mip) in mips.iter_mut().enumerate() {
+            let size = format.level_size(width >> level, height >> level);
+            let data = image
+                .data
+                .get(offset..offset + size)
+                .ok_or(Error::ProcessingFailed("DDS data is truncated".to_owned()))?;
+            mip.append(&mut convert(data, format.conversion));
+            offset += size;
+        }
+    }
+
+    Ok(ImageAsset {
+        format: format.format,
+        dimensions: [width, height],
+        layers,
+        cubemap,
+        mips,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use ash::vk;
+    use ddsfile::{
+        AlphaMode, Caps2, D3D10ResourceDimension, D3DFormat, Dds, DxgiFormat, NewD3dParams,
+        NewDxgiParams,
+    };
+
+    use super::process_dds;
+
+    #[test]
+    fn cubemap() {
+        let mut dds = Dds::new_dxgi(NewDxgiParams {
+            height: 8,
+            width: 8,
+            depth: None,
+            format: DxgiFormat::BC7_UNorm_sRGB,
+            mipmap_levels: Some(2),
+            array_layers: Some(6),
+            caps2: Some(Caps2::CUBEMAP | Caps2::CUBEMAP_ALLFACES),
+            is_cubemap: true,
+            resource_dimension: D3D10ResourceDimension::Texture2D,
+            alpha_mode: AlphaMode::Straight,
+        })
+        .unwrap();
+        dds.data
+            .chunks_exact_mut(80)
+            .enumerate()
+            .for_each(|(face, data)| data.fill(face as u8));
+        let image = process_dds(&dds, false).unwrap();
+        assert_eq!(vk::Format::BC7_SRGB_BLOCK, image.format);
+        assert_eq!(6, image.layers);
+        assert!(image.cubemap);
+        assert_eq!(6 * 64, image.mips[0].len());
+        assert_eq!(6 * 16, image.mips[1].len());
+        assert_eq!(5, image.mips[1][16 * 5]);
+    }
+
+    #[test]
+    fn legacy_fourcc() {
+        let dds = Dds::new_d3d(NewD3dParams {
+            height: 4,
+            width: 4,
+            depth: None,
+            format: D3DFormat::DXT5,
+            mipmap_levels: None,
+            caps2: None,
+        })
+        .unwrap();
+        assert_eq!(
+            vk::Format::BC3_SRGB_BLOCK,
+            process_dds(&dds, true).unwrap().format
+        );
+        assert_eq!(
+            vk::Format::BC3_UNORM_BLOCK,
+            process_dds(&dds, false).unwrap().format
+        );
+    }
+}
@@ -1,37 +1,137 @@
 use std::{
     collections::HashMap,
+    fmt::Display,
+    fs,
     path::{Path, PathBuf},
     sync::Arc,
 };
 
 use dess_assets::{
-    get_absolute_asset_path, get_relative_asset_path, AssetRef, Bone, GltfSource, ImageSource,
-    ImageSourceDesc, MeshBlendMode, MeshData, MeshMaterial, MeshVertexAttributes, ModelAsset,
-    ModelCollectionAsset, StaticMeshVertex, SubMesh,
+    get_absolute_asset_path, get_relative_asset_path, AnimationChannel, AnimationClipAsset,
+    AnimationInterpolation, AnimationValues, AssetRef, BatchedNode, Bone, BoundingVolume,
+    ChannelSource, GltfSceneSource, GltfSource, ImageDataSource, ImageSource, ImageSourceDesc,
+    MaterialClearcoat, MaterialExtensions, MaterialSheen, MaterialSpecular, MaterialTexture,
+    MaterialTransmission, MaterialVolume, MeshBlendMode, MeshCustomStream, MeshData, MeshIndexType,
+    MeshLodDesc, MeshMaterial, MeshQuantization, MeshRepair, MeshStreams, MeshVertexAttributes,
+    Meshlet, ModelAsset, ModelCollectionAsset, MorphTarget, MorphTargetDelta, NormalGeneration,
+    PackedImageSource, QuantizedMeshAttributes, QuantizedMeshVertex, SceneAsset, SceneCamera,
+    SceneExtra, SceneLight, SceneLightKind, Skin, SkinnedMeshVertex, StaticMeshVertex, SubMesh,
+    SubMeshFlags, SubMeshLod, TextureAddressMode, TextureFilter, TextureSampler, TextureTransform,
+    UpAxis,
 };
 use gltf::mesh::Mode;
 use normalize_path::NormalizePath;
 use numquant::linear::quantize;
 
-use crate::{is_asset_changed, AssetImporter, Error, ImportContext};
+use crate::{
+    animation::{
+        kept_values, lerp, quantize_rotations, reduce_keys, rotation_difference, slerp,
+        value_difference,
+    },
+    bounds::{bounding_volume, merge_volumes},
+    is_asset_changed,
+    validation::{
+        count_duplicate_vertices, normalize_vectors, sanitize_values, validate_triangles,
+        MeshReport,
+    },
+    AssetImporter, Error, ImportContext,
+};
+
+/// Malformed mesh data that can't be imported.
+#[derive(Debug)]
+enum MeshError {
+    AttributeCount {
+        mesh: usize,
+        primitive: usize,
+        semantic: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    IndexOutOfRange {
+        mesh: usize,
+        primitive: usize,
+        index: u32,
+        vertex_count: usize,
+    },
+    IncompleteTriangle {
+        mesh: usize,
+        primitive: usize,
+        index_count: usize,
+    },
+}
+
+impl Display for MeshError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AttributeCount {
+                mesh,
+                primitive,
+                semantic,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Mesh {mesh} primitive {primitive}: {semantic} has {actual} elements, expected {expected}"
+            ),
+            Self::IndexOutOfRange {
+                mesh,
+                primitive,
+                index,
+                vertex_count,
+            } => write!(
+                f,
+                "Mesh {mesh} primitive {primitive}: index {index} is out of {vertex_count} vertices"
+            ),
+            Self::IncompleteTriangle {
+                mesh,
+                primitive,
+                index_count,
+            } => write!(
+                f,
+                "Mesh {mesh} primitive {primitive}: {index_count} indices don't form triangle list"
+            ),
+        }
+    }
+}
+
+impl From<MeshError> for Error {
+    fn from(value: MeshError) -> Self {
+        Self::ProcessingFailed(value.to_string())
+    }
+}
 
 #[derive(Debug)]
 pub struct GltfContent {
     base: PathBuf,
     document: gltf::Document,
+    // Raw document for extensions that gltf crate doesn't support
+    json: serde_json::Value,
     buffers: Vec<gltf::buffer::Data>,
-    _images: Vec<gltf::image::Data>,
 }
 
-fn import_gltf(source: &GltfSource) -> Result<GltfContent, Error> {
-    let path = Path::new(&source.path).to_owned();
-    let (document, buffers, images) = gltf::import(get_absolute_asset_path(&path)?)
+fn import_gltf(path: &str) -> Result<GltfContent, Error> {
+    let path = Path::new(path).to_owned();
+    let absolute_path = get_absolute_asset_path(&path)?;
+    // Images are imported separately as assets, so only buffers are loaded here.
+    let bytes = fs::read(&absolute_path)?;
+    let gltf =
+        gltf::Gltf::from_slice(&bytes).map_err(|err| Error::ProcessingFailed(err.to_string()))?;
+    let json = if bytes.starts_with(b"glTF") {
+        let glb = gltf::Glb::from_slice(&bytes)
+            .map_err(|err| Error::ProcessingFailed(err.to_string()))?;
+        serde_json::from_slice(&glb.json)
+    } else {
+        serde_json::from_slice(&bytes)
+    }
+    .map_err(|err| Error::ProcessingFailed(err.to_string()))?;
+    let buffers = gltf::import_buffers(&gltf.document, absolute_path.parent(), gltf.blob)
         .map_err(|err| Error::ProcessingFailed(err.to_string()))?;
+    let document = gltf.document;
     let base = get_relative_asset_path(&path)?.parent().unwrap().into();
     Ok(GltfContent {
         document,
+        json,
         buffers,
-        _images: images,
         base,
     })
 }
@@ -70,83 +170,754 @@ impl<'a> mikktspace::Geometry for TangentCalcContext<'a> {
     }
 }
 
+/// Change of basis from source file coordinates to engine ones. Geometry and
+/// node transforms are converted together, so hierarchy stays the same.
+#[derive(Debug, Clone, Copy)]
+struct CoordinateConversion {
+    /// Orthonormal, but can be mirroring.
+    basis: glam::Mat3,
+    scale: f32,
+}
+
+impl CoordinateConversion {
+    fn new(scale: f32, up_axis: UpAxis, flip_handedness: bool) -> Self {
+        let basis = match up_axis {
+            UpAxis::Y => glam::Mat3::IDENTITY,
+            // (x, y, z) -> (x, z, -y)
+            UpAxis::Z => glam::Mat3::from_cols(glam::Vec3::X, -glam::Vec3::Z, glam::Vec3::Y),
+        };
+        let basis = if flip_handedness {
+            glam::Mat3::from_diagonal(glam::vec3(1.0, 1.0, -1.0)) * basis
+        } else {
+            basis
+        };
+
+        Self { basis, scale }
+    }
+
+    fn matrix(&self) -> glam::Mat4 {
+        glam::Mat4::from_mat3(self.basis * self.scale)
+    }
+
+    /// Same transform expressed in converted coordinates.
+    fn transform(&self, value: glam::Mat4) -> glam::Mat4 {
+        self.matrix() * value * self.matrix().inverse()
+    }
+
+    fn translation(&self, value: [f32; 3]) -> [f32; 3] {
+        (self.basis * glam::Vec3::from(value) * self.scale).to_array()
+    }
+
+    /// Linear in quaternion components, so works for spline tangents too.
+    fn rotation(&self, value: [f32; 4]) -> [f32; 4] {
+        // Mirroring commutes with rotation, so only proper rotation part matters
+        let basis = if self.basis.determinant() < 0.0 {
+            -self.basis
+        } else {
+            self.basis
+        };
+        let rotation = glam::Quat::from_mat3(&basis);
+
+        (rotation * glam::Quat::from_array(value) * rotation.conjugate()).to_array()
+    }
+
+    /// Basis only swaps and negates axes, so scale components are swapped.
+    fn scale(&self, value: [f32; 3]) -> [f32; 3] {
+        let basis = glam::Mat3::from_cols(
+            self.basis.x_axis.abs(),
+            self.basis.y_axis.abs(),
+            self.basis.z_axis.abs(),
+        );
+
+        (basis * glam::Vec3::from(value)).to_array()
+    }
+
+    /// Rotation that keeps cameras and lights looking along converted -Z with
+    /// converted Y up. Same as basis unless it's mirroring, in which case
+    /// mirrored frame can't be expressed by rotation of their node alone.
+    fn view_rotation(&self) -> glam::Quat {
+        let up = self.basis * glam::Vec3::Y;
+        let back = self.basis * glam::Vec3::Z;
+
+        glam::Quat::from_mat3(&glam::Mat3::from_cols(up.cross(back), up, back))
+    }
+}
+
 struct SceneProcessingContext<'a> {
     ctx: &'a dyn ImportContext,
+    path: &'a Path,
+    lods: &'a [MeshLodDesc],
+    meshlets: bool,
+    quantize: bool,
+    normals: NormalGeneration,
+    repair: MeshRepair,
+    conversion: CoordinateConversion,
+    bake_root_transform: bool,
+    batch: bool,
     model: &'a mut ModelCollectionAsset,
     scene: &'a mut ModelAsset,
+    // Shared by all models of collection
+    document: &'a gltf::Document,
+    json: &'a serde_json::Value,
     buffers: &'a Vec<gltf::buffer::Data>,
     base: &'a Path,
     // Index in gltf -> index in asset
-    processed_meshes: HashMap<u32, u32>,
-    unique_materials: HashMap<MeshMaterial, u32>,
+    // Mesh index and bits of transform baked into vertices -> index in asset
+    processed_meshes: &'a mut HashMap<(u32, [u32; 16]), u32>,
+    // Node index -> transform baked into it, `None` for baked roots themselves.
+    // Used to fix animations of these nodes.
+    baked_nodes: &'a mut HashMap<usize, Option<glam::Mat4>>,
+    // Node index and transform of baked root of this model, joints bound to
+    // it need the transform folded into inverse bind matrices
+    baked_root: Option<(usize, glam::Mat4)>,
+    unique_materials: &'a mut HashMap<MeshMaterial, u32>,
+    // Index of node in gltf -> bone index
+    node_to_bone: HashMap<usize, u32>,
+    // Node path, mesh and transform to model space of meshes merged after all
+    // nodes are processed
+    batch_parts: Vec<(String, gltf::Mesh<'a>, glam::Mat4)>,
+    // Skins are resolved after all bones are known, index in vec is index in asset
+    skins: Vec<gltf::Skin<'a>>,
 }
 
-#[allow(dead_code)]
-fn quantize_values(data: &[f32]) -> (f32, f32, Vec<u16>) {
-    let min = data
-        .iter()
-        .min_by(|x, y| x.total_cmp(y))
-        .copied()
-        .unwrap_or(0.0) as f64;
-    let max = data
-        .iter()
-        .max_by(|x, y| x.total_cmp(y))
-        .copied()
-        .unwrap_or(0.0) as f64;
-    let min = (min.abs() as u64).next_power_of_two() as f64 * min.signum();
-    let max = (max.abs() as u64).next_power_of_two() as f64 * max.signum();
-    let result = data
+/// Quantizes positions to 16 bit normalized values, returns offset and
+/// scale to restore them: position = offset + value * scale.
+pub(crate) fn quantize_positions(input: &[[f32; 3]]) -> ([f32; 3], [f32; 3], Vec<[u16; 4]>) {
+    let mut min = [f32::MAX; 3];
+    let mut max = [f32::MIN; 3];
+    for position in input {
+        for axis in 0..3 {
+            min[axis] = min[axis].min(position[axis]);
+            max[axis] = max[axis].max(position[axis]);
+        }
+    }
+    if input.is_empty() {
+        min = [0.0; 3];
+        max = [0.0; 3];
+    }
+    let scale = [0, 1, 2].map(|axis| (max[axis] - min[axis]).max(f32::EPSILON));
+    let result = input
         .iter()
-        .map(|x| quantize(*x as _, min..max, u16::MAX))
+        .map(|x| {
+            let [x, y, z] = [0, 1, 2].map(|axis| {
+                quantize(
+                    ((x[axis] - min[axis]) / scale[axis]) as f64,
+                    0.0..1.0,
+                    u16::MAX,
+                )
+            });
+            [x, y, z, 0]
+        })
         .collect::<Vec<_>>();
 
-    (min as f32, max as f32, result)
+    (min, scale, result)
+}
+
+pub(crate) fn quantize_uvs(input: &[[f32; 2]]) -> Vec<[u16; 2]> {
+    input
+        .iter()
+        .map(|x| x.map(meshopt::quantize_half))
+        .collect()
 }
 
-#[allow(dead_code)]
-fn quantize_input<const N: usize>(input: &[[f32; N]]) -> (f32, f32, Vec<[u16; N]>) {
-    let mut data = Vec::with_capacity(input.len() * N);
+/// Octahedral encoding of unit vectors into two signed normalized values.
+pub(crate) fn quantize_normalized(input: &[[f32; 3]]) -> Vec<[i16; 2]> {
     input
         .iter()
-        .for_each(|x| x.iter().for_each(|x| data.push(*x)));
-    let (min, max, values) = quantize_values(&data);
-    let mut result = Vec::with_capacity(input.len());
-    for index in 0..values.len() / N {
-        let start = index * N;
-        let mut value: [u16; N] = [0u16; N];
-        let src = &values[start..start + N];
-        (0..N).for_each(|i| {
-            value[i] = src[i];
+        .map(|x| {
+            let v = glam::Vec3::from(*x).normalize_or_zero();
+            let v = v / (v.x.abs() + v.y.abs() + v.z.abs()).max(f32::EPSILON);
+            let (x, y) = if v.z >= 0.0 {
+                (v.x, v.y)
+            } else {
+                (
+                    (1.0 - v.y.abs()) * v.x.signum(),
+                    (1.0 - v.x.abs()) * v.y.signum(),
+                )
+            };
+            [
+                meshopt::quantize_snorm(x, 16) as i16,
+                meshopt::quantize_snorm(y, 16) as i16,
+            ]
+        })
+        .collect()
+}
+
+fn process_model<'a>(
+    ctx: &mut SceneProcessingContext<'a>,
+    root: gltf::Node<'a>,
+) -> Result<(), Error> {
+    process_node(ctx, "", None, None, glam::Mat4::IDENTITY, root)?;
+    process_batches(ctx)?;
+    process_skins(ctx);
+    process_model_bounds(ctx);
+
+    Ok(())
+}
+
+/// Skinned meshes are already in model space in bind pose, others are placed
+/// by their bones.
+fn process_model_bounds(ctx: &mut SceneProcessingContext) {
+    let mut world = Vec::<glam::Mat4>::with_capacity(ctx.scene.bones.len());
+    for bone in &ctx.scene.bones {
+        let local = glam::Mat4::from_scale_rotation_translation(
+            bone.local_scale.into(),
+            glam::Quat::from_array(bone.local_rotation),
+            bone.local_translation.into(),
+        );
+        // Parents always go before children
+        world.push(
+            bone.parent
+                .map(|parent| world[parent as usize] * local)
+                .unwrap_or(local),
+        );
+    }
+    // Skinned vertices are placed by joints, so mesh is bounded in bind pose
+    // by its bounds moved with every joint.
+    let bind_pose = ctx
+        .scene
+        .node_to_skin
+        .iter()
+        .map(|(bone, skin)| {
+            let skin = &ctx.scene.skins[*skin as usize];
+            let joints = skin
+                .joints
+                .iter()
+                .zip(skin.inverse_bind_matrices.iter())
+                .map(|(joint, matrix)| {
+                    world[*joint as usize] * glam::Mat4::from_cols_array_2d(matrix)
+                })
+                .collect::<Vec<_>>();
+            (*bone, joints)
+        })
+        .collect::<HashMap<_, _>>();
+    let meshes = &ctx.model.meshes;
+    ctx.scene.bounds = merge_volumes(ctx.scene.node_to_mesh.iter().flat_map(|(bone, mesh)| {
+        let mesh = &meshes[*mesh as usize];
+        let transforms = match bind_pose.get(bone) {
+            Some(joints) if mesh.skinned_offset.is_some() && !joints.is_empty() => joints.clone(),
+            _ => vec![world[*bone as usize]],
+        };
+        transforms
+            .into_iter()
+            .map(move |transform| (mesh.bounds, transform))
+    }));
+}
+
+fn process_skins(ctx: &mut SceneProcessingContext) {
+    for skin in &ctx.skins {
+        let joints = skin
+            .joints()
+            .map(|joint| {
+                ctx.node_to_bone
+                    .get(&joint.index())
+                    .copied()
+                    .unwrap_or_else(|| {
+                        log::warn!(
+                            "Joint {} of skin {} is not part of model",
+                            joint.index(),
+                            skin.index()
+                        );
+                        0
+                    })
+            })
+            .collect::<Vec<_>>();
+        let reader = skin.reader(|buffer| Some(&ctx.buffers[buffer.index()]));
+        let mut inverse_bind_matrices = if let Some(matrices) = reader.read_inverse_bind_matrices()
+        {
+            matrices
+                .map(|matrix| {
+                    ctx.conversion
+                        .transform(glam::Mat4::from_cols_array_2d(&matrix))
+                })
+                .collect::<Vec<_>>()
+        } else {
+            vec![glam::Mat4::IDENTITY; joints.len()]
+        };
+        // Baked root lost its transform, so joint bound to it must bind
+        // without it too. Other nodes keep their world transforms.
+        if let Some((root, transform)) = ctx.baked_root {
+            for (joint, matrix) in skin.joints().zip(inverse_bind_matrices.iter_mut()) {
+                if joint.index() == root {
+                    *matrix = transform * *matrix;
+                }
+            }
+        }
+        ctx.scene.skins.push(Skin {
+            joints,
+            inverse_bind_matrices: inverse_bind_matrices
+                .iter()
+                .map(|x| x.to_cols_array_2d())
+                .collect(),
+        });
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[repr(C)]
+struct VertexDelta {
+    position: [f32; 3],
+    normal: [f32; 3],
+    tangent: [f32; 3],
+}
+
+fn morph_target_names(mesh: &gltf::Mesh) -> Vec<String> {
+    mesh.extras()
+        .as_ref()
+        .and_then(|extras| serde_json::from_str::<serde_json::Value>(extras.get()).ok())
+        .and_then(|extras| {
+            extras.get("targetNames").and_then(|names| {
+                names.as_array().map(|names| {
+                    names
+                        .iter()
+                        .map(|name| name.as_str().unwrap_or_default().to_owned())
+                        .collect()
+                })
+            })
+        })
+        .unwrap_or_default()
+}
+
+/// Simplifies primitive for each level of detail, returns indices and deviation
+/// actually achieved in model units. Stops when simplification doesn't reduce
+/// geometry anymore.
+fn generate_lods(
+    lods: &[MeshLodDesc],
+    indices: &[u32],
+    vertices: &[StaticMeshVertex],
+    bounds: &BoundingVolume,
+) -> Vec<(Vec<u32>, f32)> {
+    let adapter = meshopt::VertexDataAdapter::new(
+        meshopt::typed_to_bytes(vertices),
+        std::mem::size_of::<StaticMeshVertex>(),
+        0,
+    )
+    .unwrap();
+    let mut result = Vec::<(Vec<u32>, f32)>::new();
+    for desc in lods {
+        let target_count = ((indices.len() as f32 * desc.ratio) as usize / 3) * 3;
+        let lod = meshopt::simplify(indices, &adapter, target_count, desc.error);
+        let previous_count = result.last().map(|x| x.0.len()).unwrap_or(indices.len());
+        // Not worth a separate level if it's almost the same as previous one
+        if lod.is_empty() || lod.len() as f32 > previous_count as f32 * 0.95 {
+            break;
+        }
+        // Coarser level is never closer to source than finer one for selection
+        let previous_error = result.last().map(|x| x.1).unwrap_or(0.0);
+        let error = lod_deviation(indices, &lod, vertices, bounds).max(previous_error);
+        result.push((lod, error));
+    }
+
+    result
+}
+
+/// Largest distance from source vertices to surface of simplified level.
+fn lod_deviation(
+    indices: &[u32],
+    lod: &[u32],
+    vertices: &[StaticMeshVertex],
+    bounds: &BoundingVolume,
+) -> f32 {
+    let position = |index: u32| glam::Vec3::from(vertices[index as usize].position);
+    let triangles = lod
+        .chunks_exact(3)
+        .map(|x| [position(x[0]), position(x[1]), position(x[2])])
+        .collect::<Vec<_>>();
+
+    // Bucket triangles into uniform grid, so vertices only test nearby ones.
+    let min = glam::Vec3::from(bounds.min);
+    let size = (glam::Vec3::from(bounds.max) - min).max(glam::Vec3::splat(f32::EPSILON));
+    let resolution = ((triangles.len() as f32).cbrt().ceil() as i32).clamp(1, 64);
+    let cell_size = size / resolution as f32;
+    let cell = |p: glam::Vec3| {
+        ((p - min) / cell_size)
+            .floor()
+            .as_ivec3()
+            .clamp(glam::IVec3::ZERO, glam::IVec3::splat(resolution - 1))
+    };
+    let slot = |cell: glam::IVec3| ((cell.z * resolution + cell.y) * resolution + cell.x) as usize;
+    let mut grid = vec![Vec::<u32>::new(); resolution.pow(3) as usize];
+    for (index, [a, b, c]) in triangles.iter().enumerate() {
+        let lo = cell(a.min(*b).min(*c));
+        let hi = cell(a.max(*b).max(*c));
+        for z in lo.z..=hi.z {
+            for y in lo.y..=hi.y {
+                for x in lo.x..=hi.x {
+                    grid[slot(glam::ivec3(x, y, z))].push(index as u32);
+                }
+            }
+        }
+    }
+
+    let mut visited = vec![false; vertices.len()];
+    let mut deviation = 0.0f32;
+    for &index in indices {
+        if std::mem::replace(&mut visited[index as usize], true) {
+            continue;
+        }
+        let point = position(index);
+        let center = cell(point);
+        let mut closest = f32::MAX;
+        // Walk rings of cells outwards until nothing closer can be found
+        // beyond them or vertex can't increase deviation anymore.
+        for ring in 0..resolution {
+            for z in -ring..=ring {
+                for y in -ring..=ring {
+                    for x in -ring..=ring {
+                        let offset = glam::ivec3(x, y, z);
+                        let neighbour = center + offset;
+                        if offset.abs().max_element() != ring
+                            || neighbour.min_element() < 0
+                            || neighbour.max_element() >= resolution
+                        {
+                            continue;
+                        }
+                        for &triangle in &grid[slot(neighbour)] {
+                            let distance =
+                                closest_point_on_triangle(point, &triangles[triangle as usize])
+                                    .distance(point);
+                            closest = closest.min(distance);
+                        }
+                    }
+                }
+            }
+            if closest <= deviation || closest <= ring as f32 * cell_size.min_element() {
+                break;
+            }
+        }
+        deviation = deviation.max(closest);
+    }
+
+    deviation
+}
+
+/// Closest point on triangle, as in Real-Time Collision Detection 5.1.5.
+fn closest_point_on_triangle(p: glam::Vec3, [a, b, c]: &[glam::Vec3; 3]) -> glam::Vec3 {
+    let (a, b, c) = (*a, *b, *c);
+    let ab = b - a;
+    let ac = c - a;
+    let d1 = ab.dot(p - a);
+    let d2 = ac.dot(p - a);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return a;
+    }
+    let d3 = ab.dot(p - b);
+    let d4 = ac.dot(p - b);
+    if d3 >= 0.0 && d4 <= d3 {
+        return b;
+    }
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return a + ab * (d1 / (d1 - d3));
+    }
+    let d5 = ab.dot(p - c);
+    let d6 = ac.dot(p - c);
+    if d6 >= 0.0 && d5 <= d6 {
+        return c;
+    }
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return a + ac * (d2 / (d2 - d6));
+    }
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+        return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+    let denom = va + vb + vc;
+    if denom <= 0.0 {
+        return a;
+    }
+
+    a + ab * (vb / denom) + ac * (vc / denom)
+}
+
+const MESHLET_MAX_VERTICES: usize = 64;
+const MESHLET_MAX_TRIANGLES: usize = 124;
+
+fn build_meshlets(
+    model: &mut ModelCollectionAsset,
+    submesh: &mut SubMesh,
+    indices: &[u32],
+    vertices: &[StaticMeshVertex],
+) {
+    let adapter = meshopt::VertexDataAdapter::new(
+        meshopt::typed_to_bytes(vertices),
+        std::mem::size_of::<StaticMeshVertex>(),
+        0,
+    )
+    .unwrap();
+    let first = submesh.first_index as usize;
+    let indices = &indices[first..first + submesh.index_count as usize];
+    let meshlets = meshopt::build_meshlets(
+        indices,
+        vertices.len(),
+        MESHLET_MAX_VERTICES,
+        MESHLET_MAX_TRIANGLES,
+    );
+    submesh.first_meshlet = model.meshlets.len() as u32;
+    submesh.meshlet_count = meshlets.len() as u32;
+    for meshlet in &meshlets {
+        let bounds = meshopt::compute_meshlet_bounds(meshlet, &adapter);
+        let vertex_count = meshlet.vertex_count as usize;
+        let triangle_count = meshlet.triangle_count as usize;
+        model.meshlets.push(Meshlet {
+            vertex_offset: model.meshlet_vertices.len() as u32,
+            triangle_offset: model.meshlet_triangles.len() as u32,
+            vertex_count: vertex_count as u32,
+            triangle_count: triangle_count as u32,
+            center: bounds.center,
+            radius: bounds.radius,
+            cone_apex: bounds.cone_apex,
+            cone_cutoff: bounds.cone_cutoff,
+            cone_axis: bounds.cone_axis,
         });
-        result.push(value);
+        model
+            .meshlet_vertices
+            .extend_from_slice(&meshlet.vertices[..vertex_count]);
+        model
+            .meshlet_triangles
+            .extend(meshlet.indices[..triangle_count].iter().flatten());
+    }
+}
+
+fn append_quantized(
+    model: &mut ModelCollectionAsset,
+    vertices: &[StaticMeshVertex],
+    attributes: &[MeshVertexAttributes],
+) -> MeshQuantization {
+    let positions = vertices.iter().map(|x| x.position).collect::<Vec<_>>();
+    let (position_offset, position_scale, positions) = quantize_positions(&positions);
+    let normals = quantize_normalized(&attributes.iter().map(|x| x.normal).collect::<Vec<_>>());
+    let tangents = quantize_normalized(&attributes.iter().map(|x| x.tangent).collect::<Vec<_>>());
+    let uvs1 = quantize_uvs(&attributes.iter().map(|x| x.uv1).collect::<Vec<_>>());
+    let uvs2 = quantize_uvs(&attributes.iter().map(|x| x.uv2).collect::<Vec<_>>());
+    model.quantized_vertices.extend(
+        positions
+            .into_iter()
+            .map(|position| QuantizedMeshVertex { position }),
+    );
+    model
+        .quantized_attributes
+        .extend((0..normals.len()).map(|index| QuantizedMeshAttributes {
+            normal: normals[index],
+            tangent: tangents[index],
+            uv1: uvs1[index],
+            uv2: uvs2[index],
+        }));
+
+    MeshQuantization {
+        position_offset,
+        position_scale,
+    }
+}
+
+/// Names and component counts of `_`-prefixed attributes of all primitives.
+fn custom_attributes<'a>(
+    primitives: impl Iterator<Item = gltf::Primitive<'a>>,
+) -> Vec<(String, u32)> {
+    let mut result: Vec<(String, u32)> = Vec::new();
+    for prim in primitives {
+        for (semantic, accessor) in prim.attributes() {
+            let gltf::Semantic::Extras(name) = semantic else {
+                continue;
+            };
+            let components = accessor.dimensions().multiplicity() as u32;
+            if components > 4 {
+                log::warn!("Custom attribute {} has too many components", name);
+                continue;
+            }
+            if let Some(existing) = result.iter_mut().find(|x| x.0 == name) {
+                existing.1 = existing.1.max(components);
+            } else {
+                result.push((name, components));
+            }
+        }
     }
 
-    (min, max, result)
+    result
 }
 
-#[allow(dead_code)]
-pub(crate) fn quantize_positions(input: &[[f32; 3]]) -> (f32, f32, Vec<[u16; 3]>) {
-    quantize_input(input)
+/// Reads attribute of any component type as floats, normalized integers are
+/// mapped to [0, 1] or [-1, 1] range.
+fn read_custom_attribute(
+    buffers: &[gltf::buffer::Data],
+    accessor: &gltf::Accessor,
+) -> Option<Vec<[f32; 4]>> {
+    use gltf::accessor::DataType;
+
+    if accessor.sparse().is_some() {
+        log::warn!("Sparse custom attributes aren't supported");
+        return None;
+    }
+    let view = accessor.view()?;
+    let data = &buffers[view.buffer().index()];
+    let data_type = accessor.data_type();
+    let components = accessor.dimensions().multiplicity().min(4);
+    let stride = view.stride().unwrap_or(accessor.size());
+    let start = view.offset() + accessor.offset();
+    let normalized = accessor.normalized();
+    (0..accessor.count())
+        .map(|index| {
+            let mut value = [0.0; 4];
+            for (component, value) in value.iter_mut().enumerate().take(components) {
+                let offset = start + index * stride + component * data_type.size();
+                let bytes = data.get(offset..offset + data_type.size())?;
+                *value = match data_type {
+                    DataType::I8 if normalized => (bytes[0] as i8 as f32 / 127.0).max(-1.0),
+                    DataType::I8 => bytes[0] as i8 as f32,
+                    DataType::U8 if normalized => bytes[0] as f32 / 255.0,
+                    DataType::U8 => bytes[0] as f32,
+                    DataType::I16 => {
+                        let x = i16::from_le_bytes([bytes[0], bytes[1]]) as f32;
+                        if normalized {
+                            (x / 32767.0).max(-1.0)
+                        } else {
+                            x
+                        }
+                    }
+                    DataType::U16 => {
+                        let x = u16::from_le_bytes([bytes[0], bytes[1]]) as f32;
+                        if normalized {
+                            x / 65535.0
+                        } else {
+                            x
+                        }
+                    }
+                    DataType::U32 => u32::from_le_bytes(bytes.try_into().unwrap()) as f32,
+                    DataType::F32 => f32::from_le_bytes(bytes.try_into().unwrap()),
+                };
+            }
+            Some(value)
+        })
+        .collect()
 }
 
-#[allow(dead_code)]
-pub(crate) fn quantize_uvs(input: &[[f32; 2]]) -> (f32, f32, Vec<[u16; 2]>) {
-    quantize_input(input)
+/// Converts strips and fans to triangle list, degenerate triangles are dropped.
+/// Returns `None` for points and lines.
+fn triangle_list(mode: Mode, indices: &[u32]) -> Option<Vec<u32>> {
+    let triangles = match mode {
+        Mode::Triangles => return Some(indices.to_vec()),
+        Mode::TriangleStrip => (2..indices.len())
+            .map(|i| {
+                if i % 2 == 0 {
+                    [indices[i - 2], indices[i - 1], indices[i]]
+                } else {
+                    [indices[i - 2], indices[i], indices[i - 1]]
+                }
+            })
+            .collect::<Vec<_>>(),
+        Mode::TriangleFan => (2..indices.len())
+            .map(|i| [indices[0], indices[i - 1], indices[i]])
+            .collect::<Vec<_>>(),
+        Mode::Points | Mode::Lines | Mode::LineLoop | Mode::LineStrip => return None,
+    };
+
+    Some(
+        triangles
+            .into_iter()
+            .filter(|[a, b, c]| a != b && b != c && a != c)
+            .flatten()
+            .collect(),
+    )
 }
 
-#[allow(dead_code)]
-pub(crate) fn quantize_normalized(input: &[[f32; 3]]) -> Vec<[u16; 2]> {
-    let data = input.iter().flatten().collect::<Vec<_>>();
-    let result = data
-        .into_iter()
-        .map(|x| quantize(x.clamp(-1.0, 1.0) as f64, -1.0..1.0, u16::MAX))
+/// Computes normal for every corner of triangle list.
+fn generate_normals(
+    positions: &[[f32; 3]],
+    indices: &[u32],
+    mode: NormalGeneration,
+) -> Vec<[f32; 3]> {
+    let face_normals = indices
+        .chunks_exact(3)
+        .map(|face| {
+            let a = glam::Vec3::from(positions[face[0] as usize]);
+            let b = glam::Vec3::from(positions[face[1] as usize]);
+            let c = glam::Vec3::from(positions[face[2] as usize]);
+            // Not normalized, so larger triangles contribute more
+            (b - a).cross(c - a)
+        })
         .collect::<Vec<_>>();
+    let face_normal = |face: usize| face_normals[face].try_normalize().unwrap_or(glam::Vec3::Z);
+    let crease_angle = match mode {
+        NormalGeneration::Flat => {
+            return (0..indices.len())
+                .map(|corner| face_normal(corner / 3).to_array())
+                .collect()
+        }
+        NormalGeneration::Smooth { crease_angle } => crease_angle,
+    };
+    // Vertices can be split by other attributes, so faces are grouped by position
+    let mut position_faces = HashMap::<[u32; 3], Vec<usize>>::new();
+    for (corner, index) in indices.iter().enumerate() {
+        let position = positions[*index as usize].map(f32::to_bits);
+        position_faces.entry(position).or_default().push(corner / 3);
+    }
+    let min_cos = crease_angle.to_radians().cos();
+    indices
+        .iter()
+        .enumerate()
+        .map(|(corner, index)| {
+            let face = corner / 3;
+            let normal = face_normal(face);
+            position_faces[&positions[*index as usize].map(f32::to_bits)]
+                .iter()
+                .filter(|other| face_normal(**other).dot(normal) >= min_cos)
+                .map(|other| face_normals[*other])
+                .sum::<glam::Vec3>()
+                .try_normalize()
+                .unwrap_or(normal)
+                .to_array()
+        })
+        .collect()
+}
 
-    result.chunks(3).map(|x| [x[0], x[1]]).collect::<Vec<_>>()
+/// Expands vertex stream so every index gets its own vertex.
+fn unweld<T: Copy>(values: &[T], indices: &[u32]) -> Vec<T> {
+    indices
+        .iter()
+        .map(|index| values[*index as usize])
+        .collect()
 }
 
-fn process_model(ctx: &mut SceneProcessingContext, root: gltf::Node) {
-    process_node(ctx, "", None, root);
+/// Applies transform to primitive, winding is flipped for mirroring transforms.
+fn transform_geometry(
+    transform: glam::Mat4,
+    positions: &mut [[f32; 3]],
+    normals: &mut [[f32; 3]],
+    tangents: &mut [[f32; 4]],
+    targets: &mut [Vec<VertexDelta>],
+    indices: &mut [u32],
+) {
+    if transform == glam::Mat4::IDENTITY {
+        return;
+    }
+    let linear = glam::Mat3::from_mat4(transform);
+    let normal_matrix = linear.inverse().transpose();
+    let mirrored = linear.determinant() < 0.0;
+    for position in positions {
+        *position = transform
+            .transform_point3(glam::Vec3::from(*position))
+            .to_array();
+    }
+    for normal in normals {
+        *normal = (normal_matrix * glam::Vec3::from(*normal))
+            .normalize_or_zero()
+            .to_array();
+    }
+    for tangent in tangents {
+        let direction =
+            (linear * glam::vec3(tangent[0], tangent[1], tangent[2])).normalize_or_zero();
+        let handedness = if mirrored { -tangent[3] } else { tangent[3] };
+        *tangent = direction.extend(handedness).to_array();
+    }
+    for delta in targets.iter_mut().flatten() {
+        delta.position = (linear * glam::Vec3::from(delta.position)).to_array();
+        delta.normal = (normal_matrix * glam::Vec3::from(delta.normal)).to_array();
+        delta.tangent = (linear * glam::Vec3::from(delta.tangent)).to_array();
+    }
+    if mirrored {
+        indices.chunks_exact_mut(3).for_each(|x| x.swap(1, 2));
+    }
 }
 
 struct ProcessedGeometry {
@@ -178,22 +949,318 @@ fn process_geometry(
     }
 }
 
+fn texture_source(
+    ctx: &SceneProcessingContext,
+    texture: &gltf::texture::Texture,
+) -> Result<ImageDataSource, Error> {
+    let image = texture.source();
+    match image.source() {
+        gltf::image::Source::Uri { uri, .. } => {
+            if let Some(data) = uri.strip_prefix("data:") {
+                let encoded = data.split_once(";base64,").map(|x| x.1).unwrap_or(data);
+                let bytes = base64::decode(encoded).map_err(|err| {
+                    Error::ProcessingFailed(format!(
+                        "Failed to decode embedded image {}: {}",
+                        image.index(),
+                        err
+                    ))
+                })?;
+                Ok(ImageDataSource::Bytes(bytes))
+            } else {
+                let image_path = ctx.base.join(uri).normalize();
+                Ok(ImageDataSource::File(
+                    image_path.to_str().unwrap().to_owned(),
+                ))
+            }
+        }
+        gltf::image::Source::View { view, .. } => {
+            let bytes = view
+                .offset()
+                .checked_add(view.length())
+                .and_then(|end| {
+                    ctx.buffers
+                        .get(view.buffer().index())?
+                        .get(view.offset()..end)
+                })
+                .ok_or_else(|| {
+                    Error::ProcessingFailed(format!(
+                        "Image {} references bytes outside of buffer {}",
+                        image.index(),
+                        view.buffer().index()
+                    ))
+                })?;
+            Ok(ImageDataSource::Bytes(bytes.to_vec()))
+        }
+    }
+}
+
+fn texture_sampler(texture: &gltf::texture::Texture) -> TextureSampler {
+    use gltf::texture::{MagFilter, MinFilter, WrappingMode};
+
+    let sampler = texture.sampler();
+    let filter = match sampler.mag_filter() {
+        Some(MagFilter::Nearest) => TextureFilter::Nearest,
+        _ => TextureFilter::Linear,
+    };
+    let mipmap_filter = match sampler.min_filter() {
+        Some(MinFilter::NearestMipmapNearest) | Some(MinFilter::LinearMipmapNearest) => {
+            TextureFilter::Nearest
+        }
+        _ => TextureFilter::Linear,
+    };
+    if sampler.wrap_s() != sampler.wrap_t() {
+        log::warn!(
+            "Texture {} has different wrapping modes for axes, using horizontal one",
+            texture.index()
+        );
+    }
+    let address_mode = match sampler.wrap_s() {
+        WrappingMode::ClampToEdge => TextureAddressMode::ClampToEdge,
+        WrappingMode::MirroredRepeat => TextureAddressMode::MirroredRepeat,
+        WrappingMode::Repeat => TextureAddressMode::Repeat,
+    };
+
+    TextureSampler {
+        filter,
+        mipmap_filter,
+        address_mode,
+    }
+}
+
+fn texture_info(texture: &gltf::texture::Info) -> (u32, TextureTransform) {
+    if let Some(transform) = texture.texture_transform() {
+        (
+            transform.tex_coord().unwrap_or(texture.tex_coord()),
+            TextureTransform {
+                offset: transform.offset(),
+                scale: transform.scale(),
+                rotation: transform.rotation(),
+            },
+        )
+    } else {
+        (texture.tex_coord(), TextureTransform::default())
+    }
+}
+
+fn material_texture(
+    texture: AssetRef,
+    source: &gltf::texture::Texture,
+    uv_set: u32,
+    transform: TextureTransform,
+) -> MaterialTexture {
+    MaterialTexture {
+        texture,
+        sampler: texture_sampler(source),
+        uv_set,
+        transform,
+    }
+}
+
 fn process_texture(
     ctx: &mut SceneProcessingContext,
     texture: &gltf::texture::Texture,
     desc: ImageSourceDesc,
-) -> AssetRef {
-    match texture.source().source() {
-        gltf::image::Source::Uri { uri, .. } => {
-            let image_path = ctx.base.join(uri).normalize();
-            ctx.ctx
-                .import(Box::new(ImageSource::from_file(image_path, desc)))
+) -> Result<AssetRef, Error> {
+    let source = texture_source(ctx, texture)?;
+    Ok(ctx.ctx.import(Box::new(ImageSource { source, desc })))
+}
+
+fn process_texture_info(
+    ctx: &mut SceneProcessingContext,
+    texture: &gltf::texture::Info,
+    desc: ImageSourceDesc,
+) -> Result<MaterialTexture, Error> {
+    let (uv_set, transform) = texture_info(texture);
+    Ok(material_texture(
+        process_texture(ctx, &texture.texture(), desc)?,
+        &texture.texture(),
+        uv_set,
+        transform,
+    ))
+}
+
+fn json_float(value: &serde_json::Value, name: &str, default: f32) -> f32 {
+    value
+        .get(name)
+        .and_then(|x| x.as_f64())
+        .map(|x| x as f32)
+        .unwrap_or(default)
+}
+
+fn json_floats<const N: usize>(
+    value: &serde_json::Value,
+    name: &str,
+    default: [f32; N],
+) -> [f32; N] {
+    value
+        .get(name)
+        .and_then(|x| x.as_array())
+        .filter(|x| x.len() == N)
+        .map(|x| std::array::from_fn(|index| x[index].as_f64().unwrap_or(0.0) as f32))
+        .unwrap_or(default)
+}
+
+/// Coordinates set and KHR_texture_transform of texture info object.
+fn json_texture_info(value: &serde_json::Value) -> (u32, TextureTransform) {
+    let mut uv_set = value.get("texCoord").and_then(|x| x.as_u64()).unwrap_or(0) as u32;
+    let mut transform = TextureTransform::default();
+    if let Some(value) = value
+        .get("extensions")
+        .and_then(|x| x.get("KHR_texture_transform"))
+    {
+        if let Some(value) = value.get("texCoord").and_then(|x| x.as_u64()) {
+            uv_set = value as u32;
         }
-        _ => panic!(),
+        transform = TextureTransform {
+            offset: json_floats(value, "offset", [0.0, 0.0]),
+            scale: json_floats(value, "scale", [1.0, 1.0]),
+            rotation: json_float(value, "rotation", 0.0),
+        };
     }
+
+    (uv_set, transform)
+}
+
+/// Sampling of material texture that gltf crate doesn't expose transform for,
+/// like normal and occlusion textures.
+fn material_texture_info(
+    ctx: &SceneProcessingContext,
+    material: &gltf::Material,
+    name: &str,
+) -> (u32, TextureTransform) {
+    material
+        .index()
+        .and_then(|index| ctx.json.get("materials")?.get(index)?.get(name))
+        .map(json_texture_info)
+        .unwrap_or_default()
+}
+
+/// Texture info from raw document, for extensions that gltf crate doesn't support.
+fn process_json_texture(
+    ctx: &mut SceneProcessingContext,
+    value: &serde_json::Value,
+    name: &str,
+    desc: ImageSourceDesc,
+) -> Result<Option<MaterialTexture>, Error> {
+    let Some(value) = value.get(name) else {
+        return Ok(None);
+    };
+    let Some(texture) = value
+        .get("index")
+        .and_then(|x| x.as_u64())
+        .and_then(|x| ctx.document.textures().nth(x as usize))
+    else {
+        return Ok(None);
+    };
+    let (uv_set, transform) = json_texture_info(value);
+
+    Ok(Some(material_texture(
+        process_texture(ctx, &texture, desc)?,
+        &texture,
+        uv_set,
+        transform,
+    )))
+}
+
+/// Texture with its coordinates set and transform.
+type TextureSampling<'a> = (gltf::texture::Texture<'a>, u32, TextureTransform);
+
+/// Packed texture and separate occlusion. Occlusion is packed only when it's
+/// sampled same way as metallic-roughness texture, otherwise red channel of
+/// packed texture is white.
+fn process_occlusion_roughness_metallic(
+    ctx: &mut SceneProcessingContext,
+    material: &gltf::Material,
+) -> Result<(MaterialTexture, Option<MaterialTexture>), Error> {
+    let metallic_roughness = material
+        .pbr_metallic_roughness()
+        .metallic_roughness_texture()
+        .map(|texture| {
+            let (uv_set, transform) = texture_info(&texture);
+            (texture.texture(), uv_set, transform)
+        });
+    let occlusion = material.occlusion_texture().map(|texture| {
+        let (uv_set, transform) = material_texture_info(ctx, material, "occlusionTexture");
+        (texture.texture(), uv_set, transform)
+    });
+    let (occlusion, separate) = match (occlusion, &metallic_roughness) {
+        (Some(occlusion), Some(metallic_roughness))
+            if !same_sampling(&occlusion, metallic_roughness) =>
+        {
+            let (source, uv_set, transform) = occlusion;
+            let texture = process_texture(ctx, &source, ImageSourceDesc::non_color())?;
+            (
+                None,
+                Some(material_texture(texture, &source, uv_set, transform)),
+            )
+        }
+        (occlusion, _) => (occlusion, None),
+    };
+    let texture = process_packed_occlusion_roughness_metallic(
+        ctx,
+        occlusion.as_ref().map(|x| &x.0),
+        metallic_roughness.as_ref().map(|x| &x.0),
+    )?;
+    let packed = match metallic_roughness.or(occlusion) {
+        Some((source, uv_set, transform)) => material_texture(texture, &source, uv_set, transform),
+        None => MaterialTexture::new(texture),
+    };
+
+    Ok((packed, separate))
+}
+
+fn same_sampling(a: &TextureSampling, b: &TextureSampling) -> bool {
+    a.1 == b.1 && a.2 == b.2
+}
+
+fn process_packed_occlusion_roughness_metallic(
+    ctx: &mut SceneProcessingContext,
+    occlusion: Option<&gltf::texture::Texture>,
+    metallic_roughness: Option<&gltf::texture::Texture>,
+) -> Result<AssetRef, Error> {
+    let occlusion = occlusion
+        .map(|texture| texture_source(ctx, texture))
+        .transpose()?;
+    let metallic_roughness = metallic_roughness
+        .map(|texture| texture_source(ctx, texture))
+        .transpose()?;
+    let texture = match (occlusion, metallic_roughness) {
+        (None, None) => neutral_texture(ctx, NEUTRAL_WHITE, ImageSourceDesc::non_color()),
+        // Exporter already packed everything into single texture.
+        (Some(occlusion), Some(metallic_roughness)) if occlusion == metallic_roughness => {
+            ctx.ctx.import(Box::new(ImageSource {
+                source: occlusion,
+                desc: ImageSourceDesc::non_color(),
+            }))
+        }
+        (occlusion, metallic_roughness) => {
+            let occlusion = occlusion
+                .map(|source| ChannelSource::Image(source, 0))
+                .unwrap_or(ChannelSource::constant(1.0));
+            let (roughness, metallic) = if let Some(source) = metallic_roughness {
+                (
+                    ChannelSource::Image(source.clone(), 1),
+                    ChannelSource::Image(source, 2),
+                )
+            } else {
+                (ChannelSource::constant(1.0), ChannelSource::constant(1.0))
+            };
+            ctx.ctx.import(Box::new(PackedImageSource::new(
+                [occlusion, roughness, metallic, ChannelSource::constant(1.0)],
+                ImageSourceDesc::non_color(),
+            )))
+        }
+    };
+
+    Ok(texture)
 }
 
-fn process_placeholder(
+const NEUTRAL_WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const NEUTRAL_NORMAL: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+
+/// Used when material has no texture, same for every material so only one
+/// asset is created. Material factors are applied on top of it.
+fn neutral_texture(
     ctx: &mut SceneProcessingContext,
     color: [f32; 4],
     desc: ImageSourceDesc,
@@ -212,138 +1279,566 @@ fn process_blend(material: &gltf::Material) -> MeshBlendMode {
     }
 }
 
+fn process_extensions(
+    ctx: &mut SceneProcessingContext,
+    material: &gltf::Material,
+) -> Result<MaterialExtensions, Error> {
+    let json = ctx.json;
+    let extensions = material
+        .index()
+        .and_then(|index| json.get("materials")?.get(index)?.get("extensions"));
+    let clearcoat = match extensions.and_then(|x| x.get("KHR_materials_clearcoat")) {
+        Some(value) => Some(MaterialClearcoat {
+            factor: json_float(value, "clearcoatFactor", 0.0),
+            texture: process_json_texture(
+                ctx,
+                value,
+                "clearcoatTexture",
+                ImageSourceDesc::non_color(),
+            )?,
+            roughness_factor: json_float(value, "clearcoatRoughnessFactor", 0.0),
+            roughness_texture: process_json_texture(
+                ctx,
+                value,
+                "clearcoatRoughnessTexture",
+                ImageSourceDesc::non_color(),
+            )?,
+            normal_texture: process_json_texture(
+                ctx,
+                value,
+                "clearcoatNormalTexture",
+                ImageSourceDesc::normals(),
+            )?,
+        }),
+        None => None,
+    };
+    let sheen = match extensions.and_then(|x| x.get("KHR_materials_sheen")) {
+        Some(value) => Some(MaterialSheen {
+            color_factor: json_floats(value, "sheenColorFactor", [0.0, 0.0, 0.0]),
+            color_texture: process_json_texture(
+                ctx,
+                value,
+                "sheenColorTexture",
+                ImageSourceDesc::color(),
+            )?,
+            roughness_factor: json_float(value, "sheenRoughnessFactor", 0.0),
+            roughness_texture: process_json_texture(
+                ctx,
+                value,
+                "sheenRoughnessTexture",
+                ImageSourceDesc::non_color(),
+            )?,
+        }),
+        None => None,
+    };
+    let transmission = match material.transmission() {
+        Some(transmission) => Some(MaterialTransmission {
+            factor: transmission.transmission_factor(),
+            texture: transmission
+                .transmission_texture()
+                .map(|texture| process_texture_info(ctx, &texture, ImageSourceDesc::non_color()))
+                .transpose()?,
+        }),
+        None => None,
+    };
+    let specular = match material.specular() {
+        Some(specular) => Some(MaterialSpecular {
+            factor: specular.specular_factor(),
+            texture: specular
+                .specular_texture()
+                .map(|texture| process_texture_info(ctx, &texture, ImageSourceDesc::non_color()))
+                .transpose()?,
+            color_factor: specular.specular_color_factor(),
+            color_texture: specular
+                .specular_color_texture()
+                .map(|texture| process_texture_info(ctx, &texture, ImageSourceDesc::color()))
+                .transpose()?,
+        }),
+        None => None,
+    };
+    let volume = match material.volume() {
+        Some(volume) => Some(MaterialVolume {
+            thickness_factor: volume.thickness_factor(),
+            thickness_texture: volume
+                .thickness_texture()
+                .map(|texture| process_texture_info(ctx, &texture, ImageSourceDesc::non_color()))
+                .transpose()?,
+            attenuation_distance: volume.attenuation_distance(),
+            attenuation_color: volume.attenuation_color(),
+        }),
+        None => None,
+    };
+
+    Ok(MaterialExtensions {
+        clearcoat,
+        sheen,
+        transmission,
+        specular,
+        volume,
+        ior: material.ior(),
+        unlit: material.unlit(),
+    })
+}
+
 fn create_pbr_material(
     ctx: &mut SceneProcessingContext,
     material: &gltf::Material,
-) -> MeshMaterial {
+) -> Result<MeshMaterial, Error> {
     let base = if let Some(texture) = material.pbr_metallic_roughness().base_color_texture() {
-        process_texture(ctx, &texture.texture(), ImageSourceDesc::color())
+        process_texture_info(ctx, &texture, ImageSourceDesc::color())?
     } else {
-        process_placeholder(
+        MaterialTexture::new(neutral_texture(
             ctx,
-            material.pbr_metallic_roughness().base_color_factor(),
+            NEUTRAL_WHITE,
             ImageSourceDesc::color(),
-        )
+        ))
     };
 
-    let metallic_roughness = if let Some(texture) = material
-        .pbr_metallic_roughness()
-        .metallic_roughness_texture()
-    {
-        process_texture(ctx, &texture.texture(), ImageSourceDesc::non_color())
-    } else {
-        process_placeholder(
-            ctx,
-            [
-                0.0,
-                material.pbr_metallic_roughness().roughness_factor(),
-                material.pbr_metallic_roughness().metallic_factor(),
-                1.0,
-            ],
-            ImageSourceDesc::non_color(),
-        )
-    };
-
-    let occlusion = if let Some(texture) = material.occlusion_texture() {
-        process_texture(ctx, &texture.texture(), ImageSourceDesc::non_color())
-    } else {
-        process_placeholder(ctx, [1.0, 0.0, 0.0, 1.0], ImageSourceDesc::non_color())
-    };
+    let (occlusion_roughness_metallic, occlusion) =
+        process_occlusion_roughness_metallic(ctx, material)?;
 
     let normals = if let Some(texture) = material.normal_texture() {
-        process_texture(ctx, &texture.texture(), ImageSourceDesc::normals())
+        let (uv_set, transform) = material_texture_info(ctx, material, "normalTexture");
+        material_texture(
+            process_texture(ctx, &texture.texture(), ImageSourceDesc::normals())?,
+            &texture.texture(),
+            uv_set,
+            transform,
+        )
     } else {
-        process_placeholder(ctx, [0.0, 0.0, 1.0, 1.0], ImageSourceDesc::normals())
+        MaterialTexture::new(neutral_texture(
+            ctx,
+            NEUTRAL_NORMAL,
+            ImageSourceDesc::normals(),
+        ))
     };
 
     let emissive = if let Some(texture) = material.emissive_texture() {
-        process_texture(ctx, &texture.texture(), ImageSourceDesc::non_color())
+        process_texture_info(ctx, &texture, ImageSourceDesc::non_color())?
     } else {
-        let emissive_color = material.emissive_factor();
-        process_placeholder(
+        MaterialTexture::new(neutral_texture(
             ctx,
-            [emissive_color[0], emissive_color[1], emissive_color[2], 1.0],
+            NEUTRAL_WHITE,
             ImageSourceDesc::non_color(),
-        )
+        ))
     };
-    MeshMaterial {
+    let pbr = material.pbr_metallic_roughness();
+    Ok(MeshMaterial {
         blend: process_blend(material),
         base,
         normals,
-        metallic_roughness,
+        occlusion_roughness_metallic,
         occlusion,
         emissive,
         emission_power: material.emissive_strength().unwrap_or(0.0),
-    }
+        base_color_factor: pbr.base_color_factor(),
+        metallic_factor: pbr.metallic_factor(),
+        roughness_factor: pbr.roughness_factor(),
+        emissive_factor: material.emissive_factor(),
+        normal_scale: material.normal_texture().map(|x| x.scale()).unwrap_or(1.0),
+        occlusion_strength: material
+            .occlusion_texture()
+            .map(|x| x.strength())
+            .unwrap_or(1.0),
+        extensions: process_extensions(ctx, material)?,
+    })
 }
 
-fn process_material(ctx: &mut SceneProcessingContext, material: &gltf::Material) -> u32 {
-    let material = create_pbr_material(ctx, material);
+fn process_material(
+    ctx: &mut SceneProcessingContext,
+    material: &gltf::Material,
+) -> Result<u32, Error> {
+    let material = create_pbr_material(ctx, material)?;
     if let Some(index) = ctx.unique_materials.get(&material) {
-        *index
+        Ok(*index)
     } else {
         let index = ctx.model.materials.len();
         ctx.model.materials.push(material.clone());
         ctx.unique_materials.insert(material, index as u32);
-        index as u32
+        Ok(index as u32)
     }
 }
 
-fn process_mesh(ctx: &mut SceneProcessingContext, mesh: &gltf::Mesh) {
-    let mut target = MeshData::default();
-    let mut mesh_indices = Vec::new();
-    let mut mesh_attributes = Vec::new();
-    let mut mesh_vertices = Vec::new();
-    for prim in mesh.primitives() {
-        assert_eq!(prim.mode(), Mode::Triangles);
-        let reader = prim.reader(|buffer| Some(&ctx.buffers[buffer.index()]));
-        let positions = if let Some(positions) = reader.read_positions() {
-            positions.collect::<Vec<_>>()
-        } else {
-            return;
-        };
-        let (uvs1, has_uvs) = if let Some(texcoord) = reader.read_tex_coords(0) {
-            (texcoord.into_f32().collect::<Vec<_>>(), true)
-        } else {
-            (vec![[0.0, 0.0]; positions.len()], false)
-        };
-        let uvs2 = if let Some(texcoord) = reader.read_tex_coords(1) {
-            texcoord.into_f32().collect::<Vec<_>>()
-        } else {
-            vec![[0.0, 0.0]; positions.len()]
-        };
-        assert_eq!(positions.len(), uvs1.len());
-        let (normals, has_normals) = if let Some(normals) = reader.read_normals() {
-            (normals.collect::<Vec<_>>(), true)
-        } else {
-            (vec![[1.0, 0.0, 0.0]; positions.len()], false)
-        };
-        assert_eq!(positions.len(), normals.len());
-        let (mut tangents, has_tangents) = if let Some(tangents) = reader.read_tangents() {
-            (tangents.collect::<Vec<_>>(), true)
-        } else {
-            (vec![[0.0, 1.0, 0.0, 0.0]; positions.len()], false)
-        };
-        assert_eq!(positions.len(), tangents.len());
+/// Vertex streams of primitive in mesh space, ready for welding.
+struct PrimitiveData {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    tangents: Vec<[f32; 4]>,
+    uvs1: Vec<[f32; 2]>,
+    uvs2: Vec<[f32; 2]>,
+    skinned: Option<Vec<SkinnedMeshVertex>>,
+    targets: Vec<Vec<VertexDelta>>,
+    colors: Option<Vec<[u8; 4]>>,
+    custom: Vec<Option<Vec<[f32; 4]>>>,
+    indices: Vec<u32>,
+}
 
-        let indices = if let Some(indices) = reader.read_indices() {
-            indices.into_u32().collect::<Vec<_>>()
+fn append_optional<T: Copy>(
+    target: &mut Option<Vec<T>>,
+    other: Option<Vec<T>>,
+    count: usize,
+    other_count: usize,
+    default: T,
+) {
+    match (target.as_mut(), other) {
+        (Some(target), Some(mut other)) => target.append(&mut other),
+        (Some(target), None) => target.resize(count + other_count, default),
+        (None, Some(other)) => {
+            let mut values = vec![default; count];
+            values.extend(other);
+            *target = Some(values);
+        }
+        (None, None) => {}
+    }
+}
+
+impl PrimitiveData {
+    /// Streams missing in one of primitives are filled with defaults.
+    fn append(&mut self, mut other: Self) {
+        let count = self.positions.len();
+        let other_count = other.positions.len();
+        self.indices
+            .extend(other.indices.iter().map(|x| x + count as u32));
+        self.positions.append(&mut other.positions);
+        self.normals.append(&mut other.normals);
+        self.tangents.append(&mut other.tangents);
+        self.uvs1.append(&mut other.uvs1);
+        self.uvs2.append(&mut other.uvs2);
+        append_optional(
+            &mut self.skinned,
+            other.skinned,
+            count,
+            other_count,
+            SkinnedMeshVertex::default(),
+        );
+        let target_count = self.targets.len().max(other.targets.len());
+        self.targets
+            .resize(target_count, vec![VertexDelta::default(); count]);
+        other
+            .targets
+            .resize(target_count, vec![VertexDelta::default(); other_count]);
+        for (target, mut other) in self.targets.iter_mut().zip(other.targets) {
+            target.append(&mut other);
+        }
+        append_optional(&mut self.colors, other.colors, count, other_count, [255; 4]);
+        for (values, other) in self.custom.iter_mut().zip(other.custom) {
+            append_optional(values, other, count, other_count, [0.0; 4]);
+        }
+    }
+}
+
+/// Reads, validates and transforms primitive. Returns `None` for primitives
+/// that can't be rendered as triangles.
+fn read_primitive(
+    ctx: &SceneProcessingContext,
+    mesh: &gltf::Mesh,
+    prim: &gltf::Primitive,
+    transform: glam::Mat4,
+    custom_names: &[(String, u32)],
+    report: &mut MeshReport,
+) -> Result<Option<PrimitiveData>, Error> {
+    if !matches!(
+        prim.mode(),
+        Mode::Triangles | Mode::TriangleStrip | Mode::TriangleFan
+    ) {
+        log::warn!(
+            "Mesh {} primitive {}: {:?} primitives aren't supported, skipping",
+            mesh.index(),
+            prim.index(),
+            prim.mode()
+        );
+        return Ok(None);
+    }
+    let reader = prim.reader(|buffer| Some(&ctx.buffers[buffer.index()]));
+    let mut positions = if let Some(positions) = reader.read_positions() {
+        positions.collect::<Vec<_>>()
+    } else {
+        log::warn!(
+            "Mesh {} primitive {} has no positions, skipping",
+            mesh.index(),
+            prim.index()
+        );
+        return Ok(None);
+    };
+    let check_count = |semantic, actual| {
+        if actual == positions.len() {
+            Ok(())
         } else {
-            return;
-        };
+            Err(MeshError::AttributeCount {
+                mesh: mesh.index(),
+                primitive: prim.index(),
+                semantic,
+                expected: positions.len(),
+                actual,
+            })
+        }
+    };
+    let (mut uvs1, has_uvs) = if let Some(texcoord) = reader.read_tex_coords(0) {
+        (texcoord.into_f32().collect::<Vec<_>>(), true)
+    } else {
+        (vec![[0.0, 0.0]; positions.len()], false)
+    };
+    let mut uvs2 = if let Some(texcoord) = reader.read_tex_coords(1) {
+        texcoord.into_f32().collect::<Vec<_>>()
+    } else {
+        vec![[0.0, 0.0]; positions.len()]
+    };
+    check_count("TEXCOORD_0", uvs1.len())?;
+    check_count("TEXCOORD_1", uvs2.len())?;
+    let (mut normals, has_normals) = if let Some(normals) = reader.read_normals() {
+        (normals.collect::<Vec<_>>(), true)
+    } else {
+        (vec![[1.0, 0.0, 0.0]; positions.len()], false)
+    };
+    check_count("NORMAL", normals.len())?;
+    let (mut tangents, has_tangents) = if let Some(tangents) = reader.read_tangents() {
+        (tangents.collect::<Vec<_>>(), true)
+    } else {
+        (vec![[0.0, 1.0, 0.0, 0.0]; positions.len()], false)
+    };
+    check_count("TANGENT", tangents.len())?;
 
-        if has_uvs && has_normals && !has_tangents {
-            mikktspace::generate_tangents(&mut TangentCalcContext {
-                indices: &indices,
-                positions: &positions,
-                normals: &normals,
-                uvs: &uvs1,
-                tangents: &mut tangents,
-            });
-        };
+    let mut skinned = match (reader.read_joints(0), reader.read_weights(0)) {
+        (Some(joints), Some(weights)) => Some(
+            joints
+                .into_u16()
+                .zip(weights.into_f32())
+                .map(|(joints, weights)| SkinnedMeshVertex::new(joints, weights))
+                .collect::<Vec<_>>(),
+        ),
+        _ => None,
+    };
+    if let Some(skinned) = &skinned {
+        check_count("JOINTS_0", skinned.len())?;
+    }
 
-        let bounds = prim.bounding_box();
-        let bounds = (bounds.min, bounds.max);
+    let mut targets = reader
+        .read_morph_targets()
+        .map(|(positions_delta, normals_delta, tangents_delta)| {
+            let mut deltas = vec![VertexDelta::default(); positions.len()];
+            if let Some(values) = positions_delta {
+                deltas
+                    .iter_mut()
+                    .zip(values)
+                    .for_each(|(x, y)| x.position = y);
+            }
+            if let Some(values) = normals_delta {
+                deltas
+                    .iter_mut()
+                    .zip(values)
+                    .for_each(|(x, y)| x.normal = y);
+            }
+            if let Some(values) = tangents_delta {
+                deltas
+                    .iter_mut()
+                    .zip(values)
+                    .for_each(|(x, y)| x.tangent = y);
+            }
+            deltas
+        })
+        .collect::<Vec<_>>();
+
+    let mut colors = reader
+        .read_colors(0)
+        .map(|colors| colors.into_rgba_u8().collect::<Vec<_>>());
+    if let Some(colors) = &colors {
+        check_count("COLOR_0", colors.len())?;
+    }
+    let mut custom = custom_names
+        .iter()
+        .map(|(name, _)| {
+            prim.attributes()
+                .find(|(semantic, _)| matches!(semantic, gltf::Semantic::Extras(x) if x == name))
+                .and_then(|(_, accessor)| read_custom_attribute(ctx.buffers, &accessor))
+                .filter(|values| values.len() == positions.len())
+        })
+        .collect::<Vec<_>>();
+
+    let indices = if let Some(indices) = reader.read_indices() {
+        indices.into_u32().collect::<Vec<_>>()
+    } else {
+        (0..positions.len() as u32).collect()
+    };
+    if let Some(index) = indices.iter().find(|x| **x as usize >= positions.len()) {
+        return Err(MeshError::IndexOutOfRange {
+            mesh: mesh.index(),
+            primitive: prim.index(),
+            index: *index,
+            vertex_count: positions.len(),
+        }
+        .into());
+    }
+    let mut indices = triangle_list(prim.mode(), &indices).unwrap_or_default();
+    if !indices.len().is_multiple_of(3) {
+        return Err(MeshError::IncompleteTriangle {
+            mesh: mesh.index(),
+            primitive: prim.index(),
+            index_count: indices.len(),
+        }
+        .into());
+    }
+
+    sanitize_values(&mut positions, ctx.repair, report);
+    sanitize_values(&mut normals, ctx.repair, report);
+    sanitize_values(&mut tangents, ctx.repair, report);
+    sanitize_values(&mut uvs1, ctx.repair, report);
+    sanitize_values(&mut uvs2, ctx.repair, report);
+    if has_normals {
+        normalize_vectors(&mut normals, ctx.repair, report);
+    }
+    if has_tangents {
+        normalize_vectors(&mut tangents, ctx.repair, report);
+    }
+    validate_triangles(&mut indices, &positions, ctx.repair, report);
+    let mut streams = vec![
+        meshopt::typed_to_bytes(&positions),
+        meshopt::typed_to_bytes(&normals),
+        meshopt::typed_to_bytes(&tangents),
+        meshopt::typed_to_bytes(&uvs1),
+        meshopt::typed_to_bytes(&uvs2),
+    ];
+    streams.extend(skinned.as_deref().map(meshopt::typed_to_bytes));
+    streams.extend(targets.iter().map(|x| meshopt::typed_to_bytes(x)));
+    streams.extend(colors.as_deref().map(meshopt::typed_to_bytes));
+    streams.extend(custom.iter().flatten().map(|x| meshopt::typed_to_bytes(x)));
+    count_duplicate_vertices(&streams, positions.len(), report);
+    transform_geometry(
+        transform,
+        &mut positions,
+        &mut normals,
+        &mut tangents,
+        &mut targets,
+        &mut indices,
+    );
+
+    if !has_normals {
+        // Normals are generated per corner, vertices are welded back by remap below
+        normals = generate_normals(&positions, &indices, ctx.normals);
+        positions = unweld(&positions, &indices);
+        uvs1 = unweld(&uvs1, &indices);
+        uvs2 = unweld(&uvs2, &indices);
+        tangents = unweld(&tangents, &indices);
+        skinned = skinned.map(|values| unweld(&values, &indices));
+        targets = targets
+            .iter()
+            .map(|values| unweld(values, &indices))
+            .collect();
+        colors = colors.map(|values| unweld(&values, &indices));
+        custom = custom
+            .into_iter()
+            .map(|values| values.map(|values| unweld(&values, &indices)))
+            .collect();
+        indices = (0..indices.len() as u32).collect();
+    }
+
+    if has_uvs && !has_tangents {
+        mikktspace::generate_tangents(&mut TangentCalcContext {
+            indices: &indices,
+            positions: &positions,
+            normals: &normals,
+            uvs: &uvs1,
+            tangents: &mut tangents,
+        });
+    };
+
+    Ok(Some(PrimitiveData {
+        positions,
+        normals,
+        tangents,
+        uvs1,
+        uvs2,
+        skinned,
+        targets,
+        colors,
+        custom,
+        indices,
+    }))
+}
+
+/// Index range of mesh part inside of cooked submesh.
+struct PartRange {
+    part: usize,
+    submesh: u32,
+    first_index: u32,
+    index_count: u32,
+}
+
+/// Cooks primitives of meshes into single `MeshData`, transforms are baked into
+/// vertices. With `merge` primitives sharing material become one submesh.
+/// Morph targets are taken from first mesh.
+fn process_mesh(
+    ctx: &mut SceneProcessingContext,
+    name: &str,
+    parts: &[(gltf::Mesh, glam::Mat4)],
+    merge: bool,
+) -> Result<Vec<PartRange>, Error> {
+    let mut target = MeshData::default();
+    let mut mesh_indices = Vec::new();
+    let mut mesh_attributes = Vec::new();
+    let mut mesh_vertices = Vec::new();
+    let mut mesh_skinned = Vec::new();
+    let mut is_skinned = false;
+    let target_count = parts
+        .iter()
+        .flat_map(|(mesh, _)| mesh.primitives())
+        .map(|prim| prim.morph_targets().len())
+        .max()
+        .unwrap_or(0);
+    let mut mesh_targets = vec![Vec::new(); target_count];
+    let mut mesh_lods = Vec::new();
+    let mut mesh_colors = Vec::new();
+    let mut has_colors = false;
+    let custom_names = custom_attributes(parts.iter().flat_map(|(mesh, _)| mesh.primitives()));
+    let mut mesh_custom = vec![Vec::new(); custom_names.len()];
+    let mut report = MeshReport::default();
+    let mut ranges = Vec::new();
+    let mut groups: Vec<Vec<(usize, gltf::Primitive)>> = Vec::new();
+    for (part, (mesh, _)) in parts.iter().enumerate() {
+        for prim in mesh.primitives() {
+            let material = prim.material().index();
+            match groups
+                .iter_mut()
+                .find(|group| merge && group[0].1.material().index() == material)
+            {
+                Some(group) => group.push((part, prim)),
+                None => groups.push(vec![(part, prim)]),
+            }
+        }
+    }
+    for group in groups {
+        let mut data: Option<PrimitiveData> = None;
+        // Part index, offset and count of its indices
+        let mut group_ranges = Vec::new();
+        for (part, prim) in &group {
+            let (mesh, transform) = &parts[*part];
+            let Some(primitive) =
+                read_primitive(ctx, mesh, prim, *transform, &custom_names, &mut report)?
+            else {
+                continue;
+            };
+            let offset = data.as_ref().map(|x| x.indices.len()).unwrap_or(0);
+            group_ranges.push((*part, offset as u32, primitive.indices.len() as u32));
+            if let Some(data) = &mut data {
+                data.append(primitive);
+            } else {
+                data = Some(primitive);
+            }
+        }
+        let Some(PrimitiveData {
+            positions,
+            normals,
+            tangents,
+            uvs1,
+            uvs2,
+            skinned,
+            targets,
+            colors,
+            custom,
+            indices,
+        }) = data
+        else {
+            continue;
+        };
 
         let tangents = tangents
             .iter()
@@ -351,24 +1846,103 @@ fn process_mesh(ctx: &mut SceneProcessingContext, mesh: &gltf::Mesh) {
             .collect::<Vec<_>>();
         let processed = process_geometry(&positions, &normals, &tangents, &uvs1, &uvs2);
 
+        let mut streams = vec![
+            meshopt::VertexStream::new(processed.vertices.as_ptr()),
+            meshopt::VertexStream::new(processed.attributes.as_ptr()),
+        ];
+        if let Some(skinned) = &skinned {
+            streams.push(meshopt::VertexStream::new(skinned.as_ptr()));
+        }
+        for target in &targets {
+            streams.push(meshopt::VertexStream::new(target.as_ptr()));
+        }
+        if let Some(colors) = &colors {
+            streams.push(meshopt::VertexStream::new(colors.as_ptr()));
+        }
+        for values in custom.iter().flatten() {
+            streams.push(meshopt::VertexStream::new(values.as_ptr()));
+        }
         let (total_vertex_count, remap) = meshopt::generate_vertex_remap_multi::<()>(
             processed.vertices.len(),
-            &[
-                meshopt::VertexStream::new(processed.vertices.as_ptr()),
-                meshopt::VertexStream::new(processed.attributes.as_ptr()),
-            ],
+            &streams,
             Some(&indices),
         );
         let mut vertices =
             meshopt::remap_vertex_buffer(&processed.vertices, total_vertex_count, &remap);
         let mut attributes =
             meshopt::remap_vertex_buffer(&processed.attributes, total_vertex_count, &remap);
-        let mut indices = meshopt::remap_index_buffer(Some(&indices), total_vertex_count, &remap);
+        // Only vertices referenced by triangles are left after remap
+        let bounds = bounding_volume(
+            &vertices
+                .iter()
+                .map(|x| glam::Vec3::from(x.position))
+                .collect::<Vec<_>>(),
+        );
+        let indices = meshopt::remap_index_buffer(Some(&indices), total_vertex_count, &remap);
+        let mut flags = SubMeshFlags::default();
+        if let Some(skinned) = &skinned {
+            mesh_skinned.append(&mut meshopt::remap_vertex_buffer(
+                skinned,
+                total_vertex_count,
+                &remap,
+            ));
+            flags = flags | SubMeshFlags::SKINNED;
+            is_skinned = true;
+        } else {
+            mesh_skinned.resize(
+                mesh_skinned.len() + total_vertex_count,
+                SkinnedMeshVertex::default(),
+            );
+        }
+        for (index, mesh_target) in mesh_targets.iter_mut().enumerate() {
+            if let Some(target) = targets.get(index) {
+                mesh_target.append(&mut meshopt::remap_vertex_buffer(
+                    target,
+                    total_vertex_count,
+                    &remap,
+                ));
+            } else {
+                mesh_target.resize(
+                    mesh_target.len() + total_vertex_count,
+                    VertexDelta::default(),
+                );
+            }
+        }
+        if !targets.is_empty() {
+            flags = flags | SubMeshFlags::MORPHED;
+        }
+        if let Some(colors) = &colors {
+            mesh_colors.append(&mut meshopt::remap_vertex_buffer(
+                colors,
+                total_vertex_count,
+                &remap,
+            ));
+            has_colors = true;
+        } else {
+            mesh_colors.resize(mesh_colors.len() + total_vertex_count, [255; 4]);
+        }
+        for (mesh_values, values) in mesh_custom.iter_mut().zip(custom.iter()) {
+            if let Some(values) = values {
+                mesh_values.append(&mut meshopt::remap_vertex_buffer(
+                    values,
+                    total_vertex_count,
+                    &remap,
+                ));
+            } else {
+                mesh_values.resize(mesh_values.len() + total_vertex_count, [0.0; 4]);
+            }
+        }
 
         let first_index = mesh_indices.len() as u32;
         let index_count = indices.len() as u32;
-        let material = process_material(ctx, &prim.material());
-        mesh_indices.append(&mut indices);
+        let material = process_material(ctx, &group[0].1.material())?;
+        // Indices are relative to primitive, make them relative to mesh.
+        let base_vertex = mesh_vertices.len() as u32;
+        mesh_indices.extend(indices.iter().map(|x| x + base_vertex));
+        for (lod, error) in generate_lods(ctx.lods, &indices, &vertices, &bounds) {
+            let lod = lod.iter().map(|x| x + base_vertex).collect::<Vec<_>>();
+            mesh_lods.push((target.submeshes.len(), lod, error));
+        }
         mesh_attributes.append(&mut attributes);
         mesh_vertices.append(&mut vertices);
         target.submeshes.push(SubMesh {
@@ -376,26 +1950,253 @@ fn process_mesh(ctx: &mut SceneProcessingContext, mesh: &gltf::Mesh) {
             index_count,
             bounds,
             material,
+            flags,
+            lods: Vec::new(),
+            first_meshlet: 0,
+            meshlet_count: 0,
+        });
+        let submesh = target.submeshes.len() as u32 - 1;
+        ranges.extend(
+            group_ranges
+                .into_iter()
+                .map(|(part, offset, index_count)| PartRange {
+                    part,
+                    submesh,
+                    first_index: first_index + offset,
+                    index_count,
+                }),
+        );
+    }
+    // Simplified levels go after all base levels
+    for (submesh, mut lod, error) in mesh_lods {
+        target.submeshes[submesh].lods.push(SubMeshLod {
+            first_index: mesh_indices.len() as u32,
+            index_count: lod.len() as u32,
+            error,
+        });
+        mesh_indices.append(&mut lod);
+    }
+    let remap = meshopt::optimize_vertex_fetch_remap(&mesh_indices, mesh_vertices.len());
+    let mesh_indices = meshopt::remap_index_buffer(Some(&mesh_indices), remap.len(), &remap);
+    let mut mesh_vertices = meshopt::remap_vertex_buffer(&mesh_vertices, remap.len(), &remap);
+    target.bounds = bounding_volume(
+        &mesh_vertices
+            .iter()
+            .map(|x| glam::Vec3::from(x.position))
+            .collect::<Vec<_>>(),
+    );
+    let mut mesh_attributes = meshopt::remap_vertex_buffer(&mesh_attributes, remap.len(), &remap);
+    if ctx.meshlets {
+        for submesh in &mut target.submeshes {
+            build_meshlets(ctx.model, submesh, &mesh_indices, &mesh_vertices);
+        }
+    }
+    target.streams = MeshStreams::POSITIONS | MeshStreams::ATTRIBUTES;
+    if has_colors {
+        target.streams = target.streams | MeshStreams::COLORS;
+        target.color_offset = Some(ctx.model.colors.len() as u32);
+        ctx.model.colors.append(&mut meshopt::remap_vertex_buffer(
+            &mesh_colors,
+            remap.len(),
+            &remap,
+        ));
+    }
+    for ((name, components), values) in custom_names.into_iter().zip(mesh_custom) {
+        target.streams = target.streams | MeshStreams::CUSTOM;
+        target.custom_streams.push(MeshCustomStream {
+            name,
+            components,
+            offset: ctx.model.custom_attributes.len() as u32,
         });
+        ctx.model.custom_attributes.extend(
+            meshopt::remap_vertex_buffer(&values, remap.len(), &remap)
+                .iter()
+                .flat_map(|x| x[..components as usize].iter().copied()),
+        );
     }
-    let remap = meshopt::optimize_vertex_fetch_remap(&mesh_indices, mesh_attributes.len());
-    mesh_attributes = meshopt::remap_vertex_buffer(&mesh_attributes, mesh_attributes.len(), &remap);
-    let mut mesh_indices = mesh_indices.iter().map(|x| *x as u16).collect::<Vec<_>>();
-    target.vertex_offset = ctx.model.attributes.len() as u32;
-    target.index_offset = ctx.model.indices.len() as u32;
-    ctx.model.attributes.append(&mut mesh_attributes);
-    ctx.model.indices.append(&mut mesh_indices);
-    ctx.model.vertices.append(&mut mesh_vertices);
-    ctx.scene.static_meshes.push(target);
+    if is_skinned {
+        target.streams = target.streams | MeshStreams::SKINNED;
+        target.skinned_offset = Some(ctx.model.skinned_vertices.len() as u32);
+        ctx.model
+            .skinned_vertices
+            .append(&mut meshopt::remap_vertex_buffer(
+                &mesh_skinned,
+                remap.len(),
+                &remap,
+            ));
+    }
+    let mesh = &parts[0].0;
+    let names = morph_target_names(mesh);
+    for (index, deltas) in mesh_targets.iter().enumerate() {
+        let deltas = meshopt::remap_vertex_buffer(deltas, remap.len(), &remap);
+        let first_delta = ctx.model.morph_deltas.len() as u32;
+        ctx.model.morph_deltas.extend(
+            deltas
+                .iter()
+                .enumerate()
+                .filter(|(_, delta)| **delta != VertexDelta::default())
+                .map(|(vertex, delta)| MorphTargetDelta {
+                    vertex: vertex as u32,
+                    position: delta.position,
+                    normal: delta.normal,
+                    tangent: delta.tangent,
+                }),
+        );
+        target.morph_targets.push(MorphTarget {
+            name: names
+                .get(index)
+                .cloned()
+                .unwrap_or_else(|| format!("{}", index)),
+            first_delta,
+            delta_count: ctx.model.morph_deltas.len() as u32 - first_delta,
+        });
+    }
+    target.morph_weights = mesh
+        .weights()
+        .map(|weights| weights.to_vec())
+        .unwrap_or_else(|| vec![0.0; target_count]);
+    if ctx.quantize {
+        target.vertex_offset = ctx.model.quantized_vertices.len() as u32;
+    } else {
+        target.vertex_offset = ctx.model.vertices.len() as u32;
+    }
+    if mesh_vertices.len() > u16::MAX as usize + 1 {
+        target.index_type = MeshIndexType::U32;
+        target.index_offset = ctx.model.wide_indices.len() as u32;
+        ctx.model.wide_indices.extend(mesh_indices);
+    } else {
+        target.index_type = MeshIndexType::U16;
+        target.index_offset = ctx.model.indices.len() as u32;
+        ctx.model
+            .indices
+            .extend(mesh_indices.iter().map(|x| *x as u16));
+    }
+    if ctx.quantize {
+        target.quantization = Some(append_quantized(
+            ctx.model,
+            &mesh_vertices,
+            &mesh_attributes,
+        ));
+    } else {
+        ctx.model.attributes.append(&mut mesh_attributes);
+        ctx.model.vertices.append(&mut mesh_vertices);
+    }
+    ctx.model.meshes.push(target);
+    if !report.is_clean() {
+        ctx.ctx
+            .report_mesh(format!("{}/{}", ctx.path.display(), name), report);
+    }
+
+    Ok(ranges)
 }
 
-fn process_node(
-    ctx: &mut SceneProcessingContext,
+/// Upper bound of vertex count after import, normal generation splits all
+/// vertices and strips can have up to three times more indices.
+fn estimated_vertex_count(prim: gltf::Primitive) -> usize {
+    let positions = prim
+        .get(&gltf::Semantic::Positions)
+        .map(|x| x.count())
+        .unwrap_or(0);
+    if prim.get(&gltf::Semantic::Normals).is_some() {
+        positions
+    } else {
+        prim.indices().map(|x| x.count()).unwrap_or(positions) * 3
+    }
+}
+
+/// Merges collected static meshes into as few meshes as possible, each keeps
+/// 16 bit indices unless single node needs more.
+fn process_batches(ctx: &mut SceneProcessingContext) -> Result<(), Error> {
+    let parts = std::mem::take(&mut ctx.batch_parts);
+    let mut batches: Vec<Vec<usize>> = Vec::new();
+    let mut vertex_count = 0;
+    for (index, (_, mesh, _)) in parts.iter().enumerate() {
+        let count = mesh.primitives().map(estimated_vertex_count).sum::<usize>();
+        if batches.is_empty() || vertex_count + count > u16::MAX as usize + 1 {
+            batches.push(Vec::new());
+            vertex_count = 0;
+        }
+        batches.last_mut().unwrap().push(index);
+        vertex_count += count;
+    }
+    for batch in batches {
+        let mesh_index = ctx.model.meshes.len() as u32;
+        let meshes = batch
+            .iter()
+            .map(|index| (parts[*index].1.clone(), parts[*index].2))
+            .collect::<Vec<_>>();
+        let ranges = process_mesh(ctx, &format!("batch {}", mesh_index), &meshes, true)?;
+        // Batched vertices are in model space, i.e. space of root bone
+        ctx.scene.node_to_mesh.push((0, mesh_index));
+        ctx.scene
+            .batched_nodes
+            .extend(ranges.into_iter().map(|range| BatchedNode {
+                path: parts[batch[range.part]].0.clone(),
+                mesh: mesh_index,
+                submesh: range.submesh,
+                first_index: range.first_index,
+                index_count: range.index_count,
+            }));
+    }
+
+    Ok(())
+}
+
+/// `baked` is transform of parent that was moved into its children,
+/// `parent_model` is transform of parent to model space.
+fn process_node<'a>(
+    ctx: &mut SceneProcessingContext<'a>,
     parent_name: &str,
     parent: Option<usize>,
-    node: gltf::Node,
-) {
-    let (local_translation, local_rotation, local_scale) = node.transform().decomposed();
+    baked: Option<glam::Mat4>,
+    parent_model: glam::Mat4,
+    node: gltf::Node<'a>,
+) -> Result<(), Error> {
+    let mut mesh_transform = ctx.conversion.matrix();
+    let mut children_baked = None;
+    let (local_translation, local_rotation, local_scale) =
+        if parent.is_none() && ctx.bake_root_transform {
+            let local = ctx
+                .conversion
+                .transform(glam::Mat4::from_cols_array_2d(&node.transform().matrix()));
+            // Skinned vertices are placed by joints only, children keep
+            // baked transform, so it must not be applied twice
+            if node.skin().is_none() {
+                mesh_transform = local * mesh_transform;
+            }
+            children_baked = Some(local);
+            ctx.baked_nodes.insert(node.index(), None);
+            ctx.baked_root = Some((node.index(), local));
+            ([0.0; 3], glam::Quat::IDENTITY.to_array(), [1.0; 3])
+        } else if let Some(baked) = baked {
+            ctx.baked_nodes.insert(node.index(), Some(baked));
+            let local = ctx
+                .conversion
+                .transform(glam::Mat4::from_cols_array_2d(&node.transform().matrix()));
+            let (scale, rotation, translation) = (baked * local).to_scale_rotation_translation();
+            (
+                translation.to_array(),
+                rotation.to_array(),
+                scale.to_array(),
+            )
+        } else {
+            let (translation, rotation, scale) = node.transform().decomposed();
+            (
+                ctx.conversion.translation(translation),
+                ctx.conversion.rotation(rotation),
+                ctx.conversion.scale(scale),
+            )
+        };
+    let model_transform = if parent.is_some() {
+        parent_model
+            * glam::Mat4::from_scale_rotation_translation(
+                local_scale.into(),
+                glam::Quat::from_array(local_rotation),
+                local_translation.into(),
+            )
+    } else {
+        glam::Mat4::IDENTITY
+    };
     let last = ctx.scene.bones.len();
     ctx.scene.bones.push(Bone {
         parent: parent.map(|x| x as u32),
@@ -406,29 +2207,256 @@ fn process_node(
     let name = node.name().unwrap_or(&format!("{}", last)).to_owned();
     let node_name = format!("{}/{}", parent_name, name);
     ctx.scene.bone_names.insert(node_name.clone(), last as u32);
-    if let Some(mesh) = node.mesh() {
-        if let Some(instance_index) = ctx.processed_meshes.get(&(mesh.index() as u32)) {
-            ctx.scene.node_to_mesh.push((last as u32, *instance_index));
+    ctx.node_to_bone.insert(node.index(), last as u32);
+    if let Some(skin) = node.skin() {
+        let skin_index =
+            if let Some(index) = ctx.skins.iter().position(|x| x.index() == skin.index()) {
+                index
+            } else {
+                ctx.skins.push(skin);
+                ctx.skins.len() - 1
+            };
+        ctx.scene
+            .node_to_skin
+            .push((last as u32, skin_index as u32));
+    }
+    let static_mesh = node.skin().is_none()
+        && node.mesh().is_some_and(|mesh| {
+            mesh.primitives()
+                .all(|prim| prim.morph_targets().len() == 0)
+        });
+    if let (Some(mesh), true) = (node.mesh(), ctx.batch && static_mesh) {
+        ctx.batch_parts
+            .push((node_name.clone(), mesh, model_transform * mesh_transform));
+    } else if let Some(mesh) = node.mesh() {
+        let key = (
+            mesh.index() as u32,
+            mesh_transform.to_cols_array().map(f32::to_bits),
+        );
+        let mesh_index = if let Some(mesh_index) = ctx.processed_meshes.get(&key) {
+            *mesh_index
         } else {
-            let mesh_index = ctx.scene.static_meshes.len();
-            process_mesh(ctx, &mesh);
-            ctx.scene
-                .node_to_mesh
-                .push((last as u32, mesh_index as u32));
-            ctx.scene.mesh_names.insert(
-                mesh.name().unwrap_or(&format!("{}", mesh_index)).to_owned(),
-                mesh_index as u32,
-            );
-        }
+            let mesh_index = ctx.model.meshes.len() as u32;
+            process_mesh(
+                ctx,
+                mesh.name().unwrap_or(&format!("{}", mesh.index())),
+                &[(mesh.clone(), mesh_transform)],
+                false,
+            )?;
+            ctx.processed_meshes.insert(key, mesh_index);
+            mesh_index
+        };
+        ctx.scene.node_to_mesh.push((last as u32, mesh_index));
+        ctx.scene.mesh_names.insert(
+            mesh.name().unwrap_or(&format!("{}", mesh_index)).to_owned(),
+            mesh_index,
+        );
     }
 
     for child in node.children() {
-        process_node(ctx, &node_name, Some(last), child);
+        process_node(
+            ctx,
+            &node_name,
+            Some(last),
+            children_baked,
+            model_transform,
+            child,
+        )?;
     }
+
+    Ok(())
 }
 
-fn process_model_collection(gltf: GltfContent, ctx: &dyn ImportContext) -> ModelCollectionAsset {
+/// Applies transform baked into node to its animated property, so animation
+/// doesn't undo the bake. Rotation and scale can be baked only when transform
+/// has uniform scale.
+fn bake_channel(
+    values: &mut [f32],
+    property: gltf::animation::Property,
+    interpolation: AnimationInterpolation,
+    transform: glam::Mat4,
+) -> Result<(), String> {
+    let (scale, rotation, _) = transform.to_scale_rotation_translation();
+    let uniform = scale.max_element() - scale.min_element() <= scale.max_element() * 1e-4;
+    match property {
+        gltf::animation::Property::Translation => {
+            for (index, value) in values.chunks_exact_mut(3).enumerate() {
+                // Spline tangents are directions
+                let is_tangent =
+                    interpolation == AnimationInterpolation::CubicSpline && index % 3 != 1;
+                let baked = if is_tangent {
+                    transform.transform_vector3(glam::Vec3::from_slice(value))
+                } else {
+                    transform.transform_point3(glam::Vec3::from_slice(value))
+                };
+                value.copy_from_slice(&baked.to_array());
+            }
+        }
+        gltf::animation::Property::Rotation if uniform => {
+            for value in values.chunks_exact_mut(4) {
+                let baked = rotation * glam::Quat::from_slice(value);
+                value.copy_from_slice(&baked.to_array());
+            }
+        }
+        gltf::animation::Property::Scale if uniform => {
+            values.iter_mut().for_each(|x| *x *= scale.x);
+        }
+        gltf::animation::Property::MorphTargetWeights => {}
+        _ => return Err("baked transform has non-uniform scale".into()),
+    }
+
+    Ok(())
+}
+
+fn process_animation(
+    animation: &gltf::Animation,
+    buffers: &[gltf::buffer::Data],
+    node_paths: &HashMap<usize, String>,
+    baked_nodes: &HashMap<usize, Option<glam::Mat4>>,
+    tolerance: f32,
+    conversion: CoordinateConversion,
+) -> Result<AnimationClipAsset, Error> {
+    let mut clip = AnimationClipAsset::default();
+    for channel in animation.channels() {
+        let target = channel.target();
+        let Some(bone) = node_paths.get(&target.node().index()) else {
+            log::warn!(
+                "Animation {} targets node {} that is not part of any model",
+                animation.index(),
+                target.node().index()
+            );
+            continue;
+        };
+        let interpolation = match channel.sampler().interpolation() {
+            gltf::animation::Interpolation::Step => AnimationInterpolation::Step,
+            gltf::animation::Interpolation::Linear => AnimationInterpolation::Linear,
+            gltf::animation::Interpolation::CubicSpline => AnimationInterpolation::CubicSpline,
+        };
+        let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
+        let (Some(times), Some(outputs)) = (reader.read_inputs(), reader.read_outputs()) else {
+            continue;
+        };
+        let times = times.collect::<Vec<_>>();
+        if times.is_empty() {
+            continue;
+        }
+        let (mut values, stride, is_rotation) = match outputs {
+            gltf::animation::util::ReadOutputs::Translations(values) => (
+                values
+                    .flat_map(|x| conversion.translation(x))
+                    .collect::<Vec<_>>(),
+                3,
+                false,
+            ),
+            gltf::animation::util::ReadOutputs::Scales(values) => (
+                values.flat_map(|x| conversion.scale(x)).collect::<Vec<_>>(),
+                3,
+                false,
+            ),
+            gltf::animation::util::ReadOutputs::Rotations(values) => (
+                values
+                    .into_f32()
+                    .flat_map(|x| conversion.rotation(x))
+                    .collect::<Vec<_>>(),
+                4,
+                true,
+            ),
+            gltf::animation::util::ReadOutputs::MorphTargetWeights(values) => {
+                let values = values.into_f32().collect::<Vec<_>>();
+                let keys = if interpolation == AnimationInterpolation::CubicSpline {
+                    times.len() * 3
+                } else {
+                    times.len()
+                };
+                let stride = values.len() / keys;
+                (values, stride, false)
+            }
+        };
+        let property = target.property();
+        match baked_nodes.get(&target.node().index()) {
+            Some(_) if property == gltf::animation::Property::MorphTargetWeights => {}
+            Some(Some(transform)) => bake_channel(&mut values, property, interpolation, *transform)
+                .map_err(|err| {
+                    Error::ProcessingFailed(format!(
+                        "Animation {} can't be baked into node {}: {}",
+                        animation.index(),
+                        target.node().index(),
+                        err
+                    ))
+                })?,
+            Some(None) => {
+                return Err(Error::ProcessingFailed(format!(
+                    "Animation {} targets root node {} with baked transform",
+                    animation.index(),
+                    target.node().index()
+                )))
+            }
+            None => {}
+        }
+        let kept = if is_rotation {
+            reduce_keys(
+                &times,
+                &values,
+                stride,
+                interpolation,
+                tolerance,
+                slerp,
+                rotation_difference,
+            )
+        } else {
+            reduce_keys(
+                &times,
+                &values,
+                stride,
+                interpolation,
+                tolerance,
+                lerp,
+                value_difference,
+            )
+        };
+        let times = kept.iter().map(|x| times[*x]).collect::<Vec<_>>();
+        let values = kept_values(&values, &kept, stride, interpolation);
+        let values = match target.property() {
+            gltf::animation::Property::Translation => {
+                AnimationValues::Translation(values.chunks(3).map(|x| [x[0], x[1], x[2]]).collect())
+            }
+            gltf::animation::Property::Scale => {
+                AnimationValues::Scale(values.chunks(3).map(|x| [x[0], x[1], x[2]]).collect())
+            }
+            gltf::animation::Property::Rotation => {
+                let values = values
+                    .chunks(4)
+                    .map(|x| [x[0], x[1], x[2], x[3]])
+                    .collect::<Vec<_>>();
+                let (range, values) = quantize_rotations(&values);
+                AnimationValues::Rotation { range, values }
+            }
+            gltf::animation::Property::MorphTargetWeights => AnimationValues::MorphWeights(values),
+        };
+        clip.duration = clip.duration.max(*times.last().unwrap());
+        clip.channels.push(AnimationChannel {
+            bone: bone.clone(),
+            interpolation,
+            times,
+            values,
+        });
+    }
+
+    Ok(clip)
+}
+
+fn process_model_collection(
+    gltf: GltfContent,
+    source: &GltfSource,
+    ctx: &dyn ImportContext,
+) -> Result<ModelCollectionAsset, Error> {
     let mut collection = ModelCollectionAsset::default();
+    // Index of node in gltf -> bone path, used to bind animations
+    let mut node_paths = HashMap::new();
+    // Meshes and materials are deduplicated across all models
+    let mut processed_meshes = HashMap::new();
+    let mut baked_nodes = HashMap::new();
+    let mut unique_materials = HashMap::new();
 
     for (scene_index, scene) in gltf.document.scenes().enumerate() {
         let name = scene.name().unwrap_or(&format!("{scene_index}")).to_owned();
@@ -440,28 +2468,553 @@ fn process_model_collection(gltf: GltfContent, ctx: &dyn ImportContext) -> Model
                 .to_owned();
             let mut ctx = SceneProcessingContext {
                 ctx,
+                path: Path::new(&source.path),
+                lods: &source.lods,
+                meshlets: source.meshlets,
+                quantize: source.quantize,
+                normals: source.normals,
+                repair: source.repair,
+                conversion: CoordinateConversion::new(
+                    source.scale,
+                    source.up_axis,
+                    source.flip_handedness,
+                ),
+                bake_root_transform: source.bake_root_transform,
+                batch: source.batch,
+                batch_parts: Vec::default(),
                 model: &mut collection,
                 scene: &mut result,
+                document: &gltf.document,
+                json: &gltf.json,
                 base: &gltf.base,
                 buffers: &gltf.buffers,
-                processed_meshes: HashMap::default(),
-                unique_materials: HashMap::default(),
+                processed_meshes: &mut processed_meshes,
+                baked_nodes: &mut baked_nodes,
+                baked_root: None,
+                unique_materials: &mut unique_materials,
+                node_to_bone: HashMap::default(),
+                skins: Vec::default(),
             };
-            process_model(&mut ctx, node);
+            process_model(&mut ctx, node)?;
+            let bone_paths = ctx
+                .scene
+                .bone_names
+                .iter()
+                .map(|(path, bone)| (*bone, path))
+                .collect::<HashMap<_, _>>();
+            for (node, bone) in &ctx.node_to_bone {
+                node_paths
+                    .entry(*node)
+                    .or_insert_with(|| bone_paths[bone].clone());
+            }
             collection.models.insert(name, result);
         }
     }
 
-    collection
+    for animation in gltf.document.animations() {
+        let name = animation
+            .name()
+            .unwrap_or(&format!("{}", animation.index()))
+            .to_owned();
+        let clip = process_animation(
+            &animation,
+            &gltf.buffers,
+            &node_paths,
+            &baked_nodes,
+            source.animation_tolerance,
+            CoordinateConversion::new(source.scale, source.up_axis, source.flip_handedness),
+        )?;
+        collection.animations.insert(name, clip);
+    }
+
+    Ok(collection)
 }
 
 impl AssetImporter for GltfSource {
     fn import(&self, ctx: &dyn ImportContext) -> Result<Arc<dyn dess_assets::Asset>, Error> {
-        let content = import_gltf(self)?;
-        Ok(Arc::new(process_model_collection(content, ctx)))
+        let content = import_gltf(&self.path)?;
+        Ok(Arc::new(process_model_collection(content, self, ctx)?))
     }
 
     fn is_changed(&self, timestamp: std::time::SystemTime) -> bool {
         is_asset_changed(&self.path, timestamp)
     }
 }
+
+/// Node transforms are converted like model bones. Nodes with camera or
+/// light get extra view rotation, so they keep looking along -Z, and their
+/// children compensate it.
+fn process_scene_node(
+    scene: &mut SceneAsset,
+    conversion: &CoordinateConversion,
+    parent_name: &str,
+    parent: Option<(u32, glam::Quat)>,
+    node: gltf::Node,
+) {
+    let (translation, rotation, scale) = node.transform().decomposed();
+    let parent_view = parent.map_or(glam::Quat::IDENTITY, |(_, view)| view);
+    let view = if node.camera().is_some() || node.light().is_some() {
+        conversion.view_rotation()
+    } else {
+        glam::Quat::IDENTITY
+    };
+    // View rotation only swaps and negates axes, so scale stays diagonal
+    let view_basis = glam::Mat3::from_quat(view.conjugate());
+    let view_basis = glam::Mat3::from_cols(
+        view_basis.x_axis.abs(),
+        view_basis.y_axis.abs(),
+        view_basis.z_axis.abs(),
+    );
+    let index = scene.nodes.len() as u32;
+    scene.nodes.push(Bone {
+        parent: parent.map(|(parent, _)| parent),
+        local_translation: (parent_view.conjugate()
+            * glam::Vec3::from(conversion.translation(translation)))
+        .to_array(),
+        local_rotation: (parent_view.conjugate()
+            * glam::Quat::from_array(conversion.rotation(rotation))
+            * view)
+            .to_array(),
+        local_scale: (view_basis * glam::Vec3::from(conversion.scale(scale))).to_array(),
+    });
+    let name = node.name().unwrap_or(&format!("{}", index)).to_owned();
+    let node_name = format!("{}/{}", parent_name, name);
+    scene.node_names.insert(node_name.clone(), index);
+    if let Some(camera) = node.camera() {
+        let camera = match camera.projection() {
+            gltf::camera::Projection::Perspective(camera) => SceneCamera::Perspective {
+                yfov: camera.yfov(),
+                aspect_ratio: camera.aspect_ratio(),
+                znear: camera.znear() * conversion.scale,
+                zfar: camera.zfar().map(|x| x * conversion.scale),
+            },
+            gltf::camera::Projection::Orthographic(camera) => SceneCamera::Orthographic {
+                xmag: camera.xmag() * conversion.scale,
+                ymag: camera.ymag() * conversion.scale,
+                znear: camera.znear() * conversion.scale,
+                zfar: camera.zfar() * conversion.scale,
+            },
+        };
+        scene.node_to_camera.push((index, camera));
+    }
+    if let Some(light) = node.light() {
+        let kind = match light.kind() {
+            gltf::khr_lights_punctual::Kind::Directional => SceneLightKind::Directional,
+            gltf::khr_lights_punctual::Kind::Point => SceneLightKind::Point,
+            gltf::khr_lights_punctual::Kind::Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            } => SceneLightKind::Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            },
+        };
+        scene.node_to_light.push((
+            index,
+            SceneLight {
+                kind,
+                color: light.color(),
+                intensity: light.intensity(),
+                range: light.range().map(|x| x * conversion.scale),
+            },
+        ));
+    }
+    if let Some(extras) = node.extras() {
+        match serde_json::from_str::<serde_json::Value>(extras.get()) {
+            Ok(serde_json::Value::Object(values)) => {
+                let extras = values
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let value = match value {
+                            serde_json::Value::Bool(value) => SceneExtra::Bool(value),
+                            serde_json::Value::Number(value) => {
+                                SceneExtra::Number(value.as_f64().unwrap_or_default())
+                            }
+                            serde_json::Value::String(value) => SceneExtra::String(value),
+                            value => SceneExtra::Json(value.to_string()),
+                        };
+                        (key, value)
+                    })
+                    .collect();
+                scene.node_to_extras.push((index, extras));
+            }
+            _ => log::warn!("Extras of node {} aren't an object, skipping", node_name),
+        }
+    }
+
+    for child in node.children() {
+        process_scene_node(scene, conversion, &node_name, Some((index, view)), child);
+    }
+}
+
+fn process_scene(gltf: GltfContent, source: &GltfSceneSource) -> SceneAsset {
+    let conversion =
+        CoordinateConversion::new(source.scale, source.up_axis, source.flip_handedness);
+    let mut result = SceneAsset::default();
+    if let Some(scene) = gltf
+        .document
+        .default_scene()
+        .or_else(|| gltf.document.scenes().next())
+    {
+        for node in scene.nodes() {
+            process_scene_node(&mut result, &conversion, "", None, node);
+        }
+    }
+
+    result
+}
+
+impl AssetImporter for GltfSceneSource {
+    fn import(&self, _ctx: &dyn ImportContext) -> Result<Arc<dyn dess_assets::Asset>, Error> {
+        let content = import_gltf(&self.path)?;
+        Ok(Arc::new(process_scene(content, self)))
+    }
+
+    fn is_changed(&self, timestamp: std::time::SystemTime) -> bool {
+        is_asset_changed(&self.path, timestamp)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use gltf::mesh::Mode;
+
+    use std::path::PathBuf;
+
+    use dess_assets::{
+        AnimationInterpolation, GltfSource, MeshLodDesc, ModelCollectionAsset, NormalGeneration,
+        StaticMeshVertex, UpAxis,
+    };
+    use serde_json::json;
+
+    use crate::{bounds::bounding_volume, ContentProcessor};
+
+    use super::{
+        bake_channel, generate_lods, generate_normals, process_model_collection, triangle_list,
+        CoordinateConversion, GltfContent,
+    };
+
+    /// Imports document with `buffer` embedded as its only buffer.
+    fn import(
+        mut document: serde_json::Value,
+        buffer: &[u8],
+        source: &GltfSource,
+    ) -> ModelCollectionAsset {
+        document["asset"] = json!({ "version": "2.0" });
+        document["buffers"] = json!([{
+            "byteLength": buffer.len(),
+            "uri": format!("data:application/octet-stream;base64,{}", base64::encode(buffer)),
+        }]);
+        let gltf = gltf::Gltf::from_slice(&serde_json::to_vec(&document).unwrap()).unwrap();
+        let buffers = gltf::import_buffers(&gltf.document, None, None).unwrap();
+        let content = GltfContent {
+            base: PathBuf::new(),
+            document: gltf.document,
+            json: document,
+            buffers,
+        };
+
+        process_model_collection(content, source, &ContentProcessor::default()).unwrap()
+    }
+
+    fn triangle() -> Vec<u8> {
+        [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect()
+    }
+
+    /// Single triangle mesh on single node.
+    fn triangle_document(material: serde_json::Value) -> serde_json::Value {
+        json!({
+            "scene": 0,
+            "scenes": [{ "nodes": [0] }],
+            "nodes": [{ "mesh": 0 }],
+            "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 }, "material": 0 }] }],
+            "accessors": [{
+                "bufferView": 0,
+                "componentType": 5126,
+                "count": 3,
+                "type": "VEC3",
+                "min": [0.0, 0.0, 0.0],
+                "max": [1.0, 1.0, 0.0],
+            }],
+            "bufferViews": [{ "buffer": 0, "byteLength": 36 }],
+            "images": [{ "uri": "ao.png" }, { "uri": "mr.png" }],
+            "textures": [{ "source": 0 }, { "source": 1 }],
+            "materials": [material],
+        })
+    }
+
+    #[test]
+    fn lod_error_is_achieved_deviation() {
+        // Bumpy grid, so every level has to give up some detail
+        let size = 32;
+        let mut vertices = Vec::new();
+        for y in 0..=size {
+            for x in 0..=size {
+                let (u, v) = (x as f32 / size as f32, y as f32 / size as f32);
+                let height = 0.05 * (u * 12.0).sin() * (v * 9.0).cos();
+                vertices.push(StaticMeshVertex::new([u, v, height]));
+            }
+        }
+        let mut indices = Vec::new();
+        for y in 0..size {
+            for x in 0..size {
+                let corner = y * (size + 1) + x;
+                indices.extend([corner, corner + 1, corner + size + 1]);
+                indices.extend([corner + 1, corner + size + 2, corner + size + 1]);
+            }
+        }
+        let positions = vertices
+            .iter()
+            .map(|x| glam::Vec3::from(x.position))
+            .collect::<Vec<_>>();
+        let bounds = bounding_volume(&positions);
+        let lods = [
+            MeshLodDesc::new(0.5, 0.01),
+            MeshLodDesc::new(0.25, 0.02),
+            MeshLodDesc::new(0.1, 0.05),
+        ];
+
+        let result = generate_lods(&lods, &indices, &vertices, &bounds);
+
+        assert_eq!(result.len(), lods.len());
+        // Meshopt error is relative to largest dimension of mesh
+        let scale = glam::Vec3::from(bounds.max) - glam::Vec3::from(bounds.min);
+        let mut previous = 0.0;
+        for ((_, error), desc) in result.iter().zip(lods.iter()) {
+            assert!(*error > 0.0);
+            assert!(*error >= previous);
+            assert!(*error <= desc.error * scale.max_element());
+            previous = *error;
+        }
+    }
+
+    #[test]
+    fn strips_and_fans_become_lists() {
+        assert_eq!(
+            triangle_list(Mode::TriangleStrip, &[0, 1, 2, 3, 4]),
+            Some(vec![0, 1, 2, 1, 3, 2, 2, 3, 4])
+        );
+        assert_eq!(
+            triangle_list(Mode::TriangleFan, &[0, 1, 2, 3]),
+            Some(vec![0, 1, 2, 0, 2, 3])
+        );
+        // Degenerate triangles used to restart strips are dropped
+        assert_eq!(
+            triangle_list(Mode::TriangleStrip, &[0, 1, 2, 3, 3, 4, 4, 5, 6]),
+            Some(vec![0, 1, 2, 1, 3, 2, 4, 5, 6])
+        );
+        assert_eq!(triangle_list(Mode::Lines, &[0, 1]), None);
+    }
+
+    #[test]
+    fn normals_respect_crease_angle() {
+        // Two triangles folded by 90 degrees along shared edge 0-1
+        let positions = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+        ];
+        let indices = [0, 1, 2, 1, 0, 3];
+        let sharp = generate_normals(
+            &positions,
+            &indices,
+            NormalGeneration::Smooth { crease_angle: 60.0 },
+        );
+        assert_eq!(sharp[0], [0.0, 0.0, 1.0]);
+        assert_eq!(sharp[3], [0.0, 1.0, 0.0]);
+        let smooth = generate_normals(
+            &positions,
+            &indices,
+            NormalGeneration::Smooth {
+                crease_angle: 120.0,
+            },
+        );
+        assert!((smooth[0][1] - smooth[0][2]).abs() < 1e-6);
+        assert_eq!(smooth[0], smooth[4]);
+        // Not shared vertex keeps face normal
+        assert_eq!(smooth[2], [0.0, 0.0, 1.0]);
+        assert_eq!(
+            generate_normals(&positions, &indices, NormalGeneration::Flat)[1],
+            [0.0, 0.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn occlusion_with_other_uv_set_is_not_packed() {
+        let source = GltfSource::new("test.gltf");
+        let material = |occlusion_uv_set| {
+            json!({
+                "pbrMetallicRoughness": {
+                    "metallicRoughnessTexture": { "index": 1, "texCoord": 0 },
+                },
+                "occlusionTexture": { "index": 0, "texCoord": occlusion_uv_set },
+            })
+        };
+        let packed = import(triangle_document(material(0)), &triangle(), &source);
+        assert_eq!(packed.materials[0].occlusion, None);
+
+        let separate = import(triangle_document(material(1)), &triangle(), &source);
+        let material = &separate.materials[0];
+        assert_eq!(material.occlusion.map(|x| x.uv_set), Some(1));
+        assert_eq!(material.occlusion_roughness_metallic.uv_set, 0);
+        assert_ne!(
+            material.occlusion.map(|x| x.texture),
+            Some(material.occlusion_roughness_metallic.texture)
+        );
+    }
+
+    #[test]
+    fn normal_and_occlusion_keep_texture_transform() {
+        let transform = json!({ "KHR_texture_transform": {
+            "offset": [0.5, 0.0], "scale": [2.0, 2.0], "rotation": 0.5, "texCoord": 1,
+        } });
+        let mut document = triangle_document(json!({
+            "normalTexture": { "index": 0, "extensions": transform },
+            "occlusionTexture": { "index": 0, "extensions": transform },
+        }));
+        document["extensionsUsed"] = json!(["KHR_texture_transform"]);
+        let collection = import(document, &triangle(), &GltfSource::new("test.gltf"));
+        let material = &collection.materials[0];
+        for texture in [material.normals, material.occlusion_roughness_metallic] {
+            assert_eq!(texture.uv_set, 1);
+            assert_eq!(texture.transform.offset, [0.5, 0.0]);
+            assert_eq!(texture.transform.scale, [2.0, 2.0]);
+            assert_eq!(texture.transform.rotation, 0.5);
+        }
+    }
+
+    #[test]
+    fn skinned_mesh_on_baked_root_is_not_moved_twice() {
+        let bind = glam::Mat4::from_translation(glam::vec3(-5.0, 0.0, 0.0));
+        let mut buffer = triangle();
+        // Every vertex belongs to root joint
+        buffer.extend(
+            [0u16, 1, 0, 0]
+                .repeat(3)
+                .iter()
+                .flat_map(|x| x.to_le_bytes()),
+        );
+        buffer.extend(
+            [1.0f32, 0.0, 0.0, 0.0]
+                .repeat(3)
+                .iter()
+                .flat_map(|x| x.to_le_bytes()),
+        );
+        buffer.extend(
+            [bind, bind]
+                .iter()
+                .flat_map(|x| x.to_cols_array())
+                .flat_map(|x| x.to_le_bytes()),
+        );
+        let document = json!({
+            "scene": 0,
+            "scenes": [{ "nodes": [0] }],
+            "nodes": [
+                { "mesh": 0, "skin": 0, "translation": [5.0, 0.0, 0.0], "children": [1] },
+                {},
+            ],
+            "skins": [{ "joints": [0, 1], "inverseBindMatrices": 3 }],
+            "meshes": [{ "primitives": [{ "attributes": {
+                "POSITION": 0, "JOINTS_0": 1, "WEIGHTS_0": 2,
+            } }] }],
+            "accessors": [
+                {
+                    "bufferView": 0,
+                    "componentType": 5126,
+                    "count": 3,
+                    "type": "VEC3",
+                    "min": [0.0, 0.0, 0.0],
+                    "max": [1.0, 1.0, 0.0],
+                },
+                { "bufferView": 1, "componentType": 5123, "count": 3, "type": "VEC4" },
+                { "bufferView": 2, "componentType": 5126, "count": 3, "type": "VEC4" },
+                { "bufferView": 3, "componentType": 5126, "count": 2, "type": "MAT4" },
+            ],
+            "bufferViews": [
+                { "buffer": 0, "byteLength": 36 },
+                { "buffer": 0, "byteOffset": 36, "byteLength": 24 },
+                { "buffer": 0, "byteOffset": 60, "byteLength": 48 },
+                { "buffer": 0, "byteOffset": 108, "byteLength": 128 },
+            ],
+        });
+        let source = GltfSource::new("test.gltf").bake_root_transform(true);
+        let collection = import(document, &buffer, &source);
+        let model = collection.models.values().next().unwrap();
+        assert_eq!(collection.meshes[0].bounds.min, [0.0; 3]);
+        // Bind pose of both joints is at origin: root has lost its transform,
+        // child has got it.
+        assert!(glam::Vec3::from(model.bounds.min).abs_diff_eq(glam::Vec3::ZERO, 1e-5));
+        assert!(glam::Vec3::from(model.bounds.max).abs_diff_eq(glam::vec3(1.0, 1.0, 0.0), 1e-5));
+    }
+
+    #[test]
+    fn trs_conversion_matches_matrix_conversion() {
+        let conversion = CoordinateConversion::new(0.01, UpAxis::Z, true);
+        let translation = [1.0, 2.0, 3.0];
+        let rotation = glam::Quat::from_euler(glam::EulerRot::XYZ, 0.3, -0.7, 1.1).to_array();
+        let scale = [1.0, 2.0, 4.0];
+        let expected = conversion.transform(glam::Mat4::from_scale_rotation_translation(
+            scale.into(),
+            glam::Quat::from_array(rotation),
+            translation.into(),
+        ));
+        let converted = glam::Mat4::from_scale_rotation_translation(
+            conversion.scale(scale).into(),
+            glam::Quat::from_array(conversion.rotation(rotation)),
+            conversion.translation(translation).into(),
+        );
+        assert!(converted.abs_diff_eq(expected, 1e-5));
+        // Z up becomes Y up
+        assert!(glam::Vec3::from(conversion.translation([0.0, 0.0, 100.0]))
+            .abs_diff_eq(glam::Vec3::Y, 1e-5));
+    }
+
+    #[test]
+    fn cameras_keep_looking_along_converted_direction() {
+        let rotation = glam::Quat::from_euler(glam::EulerRot::XYZ, 0.3, -0.7, 1.1);
+        for flip in [false, true] {
+            let conversion = CoordinateConversion::new(2.0, UpAxis::Z, flip);
+            let view = conversion.view_rotation();
+            let converted = glam::Quat::from_array(conversion.rotation(rotation.to_array())) * view;
+            let basis = conversion.basis;
+            assert!(
+                (converted * -glam::Vec3::Z).abs_diff_eq(basis * (rotation * -glam::Vec3::Z), 1e-5)
+            );
+            assert!(
+                (converted * glam::Vec3::Y).abs_diff_eq(basis * (rotation * glam::Vec3::Y), 1e-5)
+            );
+        }
+    }
+
+    #[test]
+    fn baked_transform_is_applied_to_channels() {
+        let transform = glam::Mat4::from_scale_rotation_translation(
+            glam::Vec3::splat(2.0),
+            glam::Quat::IDENTITY,
+            glam::vec3(1.0, 0.0, 0.0),
+        );
+        // In tangent, value, out tangent
+        let mut values = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+        bake_channel(
+            &mut values,
+            gltf::animation::Property::Translation,
+            AnimationInterpolation::CubicSpline,
+            transform,
+        )
+        .unwrap();
+        assert_eq!(values, [2.0, 0.0, 0.0, 3.0, 0.0, 0.0, 2.0, 0.0, 0.0]);
+
+        let non_uniform = glam::Mat4::from_scale(glam::vec3(1.0, 2.0, 1.0));
+        assert!(bake_channel(
+            &mut [1.0; 3],
+            gltf::animation::Property::Scale,
+            AnimationInterpolation::Linear,
+            non_uniform,
+        )
+        .is_err());
+    }
+}
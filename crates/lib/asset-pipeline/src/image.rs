@@ -2,20 +2,24 @@ use std::{io::Cursor, path::Path, sync::Arc};
 
 use ash::vk;
 use bytes::Bytes;
-use ddsfile::{Dds, DxgiFormat};
+use ddsfile::Dds;
 use dess_assets::{
-    get_absolute_asset_path, Asset, ImageAsset, ImageDataSource, ImageRgba8Data, ImageSource,
-    ImageSourceDesc, ImageType,
+    get_absolute_asset_path, Asset, ChannelSource, ImageAsset, ImageCompression, ImageDataSource,
+    ImageRgba8Data, ImageSource, ImageSourceDesc, ImageType, PackedImageSource,
 };
 use image::{imageops::FilterType, DynamicImage, ImageBuffer, Rgba};
-use intel_tex_2::{bc5, bc7};
+use intel_tex_2::{bc1, bc3, bc5, bc7};
 
-use crate::{is_asset_changed, read_to_end, AssetImporter, Error, ImportContext};
+use crate::{
+    dds::process_dds, is_asset_changed, is_ktx2, read_ktx2, read_to_end, AssetImporter, Error,
+    ImportContext, ImportSettings,
+};
 
 #[derive(Debug)]
 pub enum RawImageData {
     Rgba(ImageRgba8Data),
     Dds(Box<Dds>),
+    Ktx2(ImageAsset),
 }
 
 #[derive(Debug)]
@@ -24,42 +28,135 @@ pub struct ImageContent {
     desc: ImageSourceDesc,
 }
 
+fn placeholder_image(pixels: &[u8; 4]) -> ImageRgba8Data {
+    ImageRgba8Data {
+        data: Bytes::copy_from_slice(pixels),
+        dimensions: [1, 1],
+    }
+}
+
+fn decode_image(bytes: &[u8]) -> Result<ImageRgba8Data, Error> {
+    let image = image::load_from_memory(bytes)?.to_rgba8();
+    let dimensions = [image.dimensions().0, image.dimensions().1];
+
+    Ok(ImageRgba8Data {
+        data: image.into_raw().into(),
+        dimensions,
+    })
+}
+
+pub(crate) fn load_rgba(source: &ImageDataSource) -> Result<ImageRgba8Data, Error> {
+    match source {
+        ImageDataSource::Bytes(bytes) => decode_image(bytes),
+        ImageDataSource::File(path) => {
+            decode_image(&read_to_end(get_absolute_asset_path(Path::new(path))?)?)
+        }
+        ImageDataSource::Placeholder(pixels) => Ok(placeholder_image(pixels)),
+    }
+}
+
 fn import_image(source: &ImageSource) -> Result<ImageContent, Error> {
     let bytes = match &source.source {
         ImageDataSource::Bytes(bytes) => bytes.clone(),
         ImageDataSource::File(path) => read_to_end(get_absolute_asset_path(Path::new(path))?)?,
         ImageDataSource::Placeholder(pixels) => {
-            let data = ImageRgba8Data {
-                data: Bytes::copy_from_slice(pixels),
-                dimensions: [1, 1],
-            };
             return Ok(ImageContent {
-                data: RawImageData::Rgba(data),
+                data: RawImageData::Rgba(placeholder_image(pixels)),
                 desc: source.desc,
             });
         }
     };
-    if let Ok(dds) = Dds::read(Cursor::new(&bytes)) {
+    if is_ktx2(&bytes) {
+        Ok(ImageContent {
+            data: RawImageData::Ktx2(read_ktx2(&bytes)?),
+            desc: source.desc,
+        })
+    } else if let Ok(dds) = Dds::read(Cursor::new(&bytes)) {
         Ok(ImageContent {
             data: RawImageData::Dds(Box::new(dds)),
             desc: source.desc,
         })
     } else {
-        let image = image::load_from_memory(&bytes)?.to_rgba8();
-        let dimensions = [image.dimensions().0, image.dimensions().1];
-
         Ok(ImageContent {
-            data: RawImageData::Rgba(ImageRgba8Data {
-                data: image.into_raw().into(),
-                dimensions,
-            }),
+            data: RawImageData::Rgba(decode_image(&bytes)?),
             desc: source.desc,
         })
     }
 }
 
+fn resize_rgba(image: ImageRgba8Data, dimensions: [u32; 2]) -> ImageRgba8Data {
+    if image.dimensions == dimensions {
+        return image;
+    }
+    let image = ImageBuffer::<Rgba<u8>, _>::from_raw(
+        image.dimensions[0],
+        image.dimensions[1],
+        image.data.to_vec(),
+    )
+    .unwrap();
+    let image = image::imageops::resize(&image, dimensions[0], dimensions[1], FilterType::Lanczos3);
+
+    ImageRgba8Data {
+        data: image.into_raw().into(),
+        dimensions,
+    }
+}
+
+fn import_packed_image(source: &PackedImageSource) -> Result<ImageContent, Error> {
+    let mut images: Vec<(&ImageDataSource, ImageRgba8Data)> = Vec::new();
+    for channel in &source.channels {
+        if let ChannelSource::Image(image, index) = channel {
+            if *index > 3 {
+                return Err(Error::ProcessingFailed(format!(
+                    "Invalid channel index {} in packed image",
+                    index
+                )));
+            }
+            if !images.iter().any(|(x, _)| *x == image) {
+                images.push((image, load_rgba(image)?));
+            }
+        }
+    }
+    // Inputs can have different resolution, scale everything to largest one.
+    let dimensions = images.iter().fold([1, 1], |acc, (_, image)| {
+        [
+            acc[0].max(image.dimensions[0]),
+            acc[1].max(image.dimensions[1]),
+        ]
+    });
+    let images = images
+        .into_iter()
+        .map(|(source, image)| (source, resize_rgba(image, dimensions)))
+        .collect::<Vec<_>>();
+
+    let mut data = vec![0u8; dimensions[0] as usize * dimensions[1] as usize * 4];
+    for (index, channel) in source.channels.iter().enumerate() {
+        match channel {
+            ChannelSource::Constant(value) => data
+                .chunks_exact_mut(4)
+                .for_each(|pixel| pixel[index] = *value),
+            ChannelSource::Image(image, channel) => {
+                let (_, image) = images.iter().find(|(x, _)| *x == image).unwrap();
+                data.chunks_exact_mut(4)
+                    .zip(image.data.chunks_exact(4))
+                    .for_each(|(pixel, src)| pixel[index] = src[*channel as usize]);
+            }
+        }
+    }
+
+    Ok(ImageContent {
+        data: RawImageData::Rgba(ImageRgba8Data {
+            data: data.into(),
+            dimensions,
+        }),
+        desc: source.desc,
+    })
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum BcMode {
+    Bc1,
+    Bc3,
     Bc5,
     Bc7,
 }
@@ -67,55 +164,38 @@ enum BcMode {
 impl BcMode {
     fn block_bytes(self) -> usize {
         match self {
+            BcMode::Bc1 => 8,
+            BcMode::Bc3 => 16,
             BcMode::Bc5 => 16,
             BcMode::Bc7 => 16,
         }
     }
 }
 
-fn process_dds(image: &Dds) -> Result<ImageAsset, Error> {
-    if let Some(format) = get_backend_format(image) {
-        let data = image
-            .get_data(0)
-            .map_err(|err| Error::ProcessingFailed(err.to_string()))?;
-        let pitch_height = image.get_pitch_height();
-        let mut offset = 0;
-        let mips = (0..image.get_num_mipmap_levels())
-            .map(|mip| {
-                let width = (image.get_width() >> mip).max(pitch_height);
-                let height = (image.get_height() >> mip).max(pitch_height);
-                let pitch = dds_util::get_pitch(image, width).unwrap();
-                let size = dds_util::get_texture_size(pitch, pitch_height, height, 1);
-                let mip = &data[offset..offset + size];
-                offset += size;
-
-                mip.to_owned()
-            })
-            .collect::<Vec<_>>();
-        Ok(ImageAsset {
-            format,
-            dimensions: [image.get_width(), image.get_height()],
-            mips,
-        })
-    } else {
-        Err(Error::ProcessingFailed(
-            "Failed to load DDS file".to_owned(),
-        ))
-    }
-}
-
-fn process_rgba(image: &ImageRgba8Data, desc: ImageSourceDesc) -> Result<ImageAsset, Error> {
+/// `max_mips` limits mip chain when mips are generated.
+pub(crate) fn process_rgba(
+    image: &ImageRgba8Data,
+    desc: ImageSourceDesc,
+    compression: ImageCompression,
+    max_mips: usize,
+) -> Result<ImageAsset, Error> {
     let dimensions = image.dimensions;
 
-    let need_compression = desc.need_compression
+    let need_compression = compression != ImageCompression::Uncompressed
         && dimensions[0] >= 4
         && dimensions[1] >= 4
         && is_pow2(dimensions[0])
         && is_pow2(dimensions[1]);
 
     let format = match desc.ty {
-        ImageType::Rgba if need_compression && desc.srgb => vk::Format::BC7_SRGB_BLOCK,
-        ImageType::Rgba if need_compression => vk::Format::BC7_UNORM_BLOCK,
+        ImageType::Rgba if need_compression => match compression {
+            ImageCompression::Bc1 if desc.srgb => vk::Format::BC1_RGB_SRGB_BLOCK,
+            ImageCompression::Bc1 => vk::Format::BC1_RGB_UNORM_BLOCK,
+            ImageCompression::Bc3 if desc.srgb => vk::Format::BC3_SRGB_BLOCK,
+            ImageCompression::Bc3 => vk::Format::BC3_UNORM_BLOCK,
+            _ if desc.srgb => vk::Format::BC7_SRGB_BLOCK,
+            _ => vk::Format::BC7_UNORM_BLOCK,
+        },
         ImageType::Rg if need_compression => vk::Format::BC5_UNORM_BLOCK,
         _ if desc.srgb => vk::Format::R8G8B8A8_SRGB,
         _ => vk::Format::R8G8B8A8_UNORM,
@@ -133,8 +213,12 @@ fn process_rgba(image: &ImageRgba8Data, desc: ImageSourceDesc) -> Result<ImageAs
     let mut current_dimensions = dimensions;
     let mut mips = Vec::new();
     if desc.generate_mips && image.width() >= 4 && image.height() >= 4 {
-        while current_dimensions[0] >= 4 && current_dimensions[1] >= 4 {
-            mips.push(prepare_image(image.as_rgba8().unwrap(), format));
+        while mips.len() < max_mips && current_dimensions[0] >= 4 && current_dimensions[1] >= 4 {
+            mips.push(prepare_image(
+                image.as_rgba8().unwrap(),
+                format,
+                compression,
+            ));
             current_dimensions[0] >>= 1;
             current_dimensions[1] >>= 1;
             image = image.resize_exact(
@@ -144,17 +228,38 @@ fn process_rgba(image: &ImageRgba8Data, desc: ImageSourceDesc) -> Result<ImageAs
             );
         }
     } else {
-        mips.push(prepare_image(image.as_rgba8().unwrap(), format));
+        mips.push(prepare_image(
+            image.as_rgba8().unwrap(),
+            format,
+            compression,
+        ));
     }
 
     Ok(ImageAsset {
         format,
         dimensions,
+        layers: 1,
+        cubemap: false,
         mips,
     })
 }
 
-fn block_compress(image: &ImageBuffer<Rgba<u8>, Vec<u8>>, bc: BcMode) -> Vec<u8> {
+fn bc7_settings(compression: ImageCompression, alpha: bool) -> bc7::EncodeSettings {
+    match (compression, alpha) {
+        (ImageCompression::Fast, false) => bc7::opaque_very_fast_settings(),
+        (ImageCompression::Fast, true) => bc7::alpha_very_fast_settings(),
+        (ImageCompression::Slow, false) => bc7::opaque_slow_settings(),
+        (ImageCompression::Slow, true) => bc7::alpha_slow_settings(),
+        (_, false) => bc7::opaque_basic_settings(),
+        (_, true) => bc7::alpha_basic_settings(),
+    }
+}
+
+fn block_compress(
+    image: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+    bc: BcMode,
+    compression: ImageCompression,
+) -> Vec<u8> {
     let block_count = intel_tex_2::divide_up_by_multiple(image.width() * image.height(), 16);
 
     let needs_alpha = bc == BcMode::Bc7 && image.pixels().any(|px| px.0[3] != 255);
@@ -171,13 +276,11 @@ fn block_compress(image: &ImageBuffer<Rgba<u8>, Vec<u8>>, bc: BcMode) -> Vec<u8>
     let mut compressed_bytes = vec![0u8; block_count as usize * block_bytes];
 
     match bc {
+        BcMode::Bc1 => bc1::compress_blocks_into(&surface, &mut compressed_bytes),
+        BcMode::Bc3 => bc3::compress_blocks_into(&surface, &mut compressed_bytes),
         BcMode::Bc5 => bc5::compress_blocks_into(&surface, &mut compressed_bytes),
         BcMode::Bc7 => {
-            let settings = if needs_alpha {
-                bc7::alpha_basic_settings()
-            } else {
-                bc7::opaque_basic_settings()
-            };
+            let settings = bc7_settings(compression, needs_alpha);
             bc7::compress_blocks_into(&settings, &surface, &mut compressed_bytes)
         }
     }
@@ -185,47 +288,36 @@ fn block_compress(image: &ImageBuffer<Rgba<u8>, Vec<u8>>, bc: BcMode) -> Vec<u8>
     compressed_bytes
 }
 
-fn prepare_image(image: &ImageBuffer<Rgba<u8>, Vec<u8>>, format: vk::Format) -> Vec<u8> {
+fn prepare_image(
+    image: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+    format: vk::Format,
+    compression: ImageCompression,
+) -> Vec<u8> {
     match format {
-        vk::Format::BC5_UNORM_BLOCK => block_compress(image, BcMode::Bc5),
+        vk::Format::BC1_RGB_SRGB_BLOCK | vk::Format::BC1_RGB_UNORM_BLOCK => {
+            block_compress(image, BcMode::Bc1, compression)
+        }
+        vk::Format::BC3_SRGB_BLOCK | vk::Format::BC3_UNORM_BLOCK => {
+            block_compress(image, BcMode::Bc3, compression)
+        }
+        vk::Format::BC5_UNORM_BLOCK => block_compress(image, BcMode::Bc5, compression),
         vk::Format::BC7_SRGB_BLOCK | vk::Format::BC7_UNORM_BLOCK => {
-            block_compress(image, BcMode::Bc7)
+            block_compress(image, BcMode::Bc7, compression)
         }
         vk::Format::R8G8B8A8_SRGB | vk::Format::R8G8B8A8_UNORM => image.to_vec(),
         _ => panic!("Unknow format: {:?}", format),
     }
 }
 
-fn get_backend_format(image: &Dds) -> Option<vk::Format> {
-    if let Some(format) = image.get_dxgi_format() {
-        match format {
-            DxgiFormat::R8G8B8A8_UNorm => return Some(vk::Format::R8G8B8A8_UNORM),
-            DxgiFormat::R8G8B8A8_UNorm_sRGB => return Some(vk::Format::R8G8B8A8_SRGB),
-            DxgiFormat::BC1_UNorm => return Some(vk::Format::BC1_RGB_UNORM_BLOCK),
-            DxgiFormat::BC1_UNorm_sRGB => return Some(vk::Format::BC1_RGBA_SRGB_BLOCK),
-            DxgiFormat::BC2_UNorm => return Some(vk::Format::BC2_UNORM_BLOCK),
-            DxgiFormat::BC2_UNorm_sRGB => return Some(vk::Format::BC2_SRGB_BLOCK),
-            DxgiFormat::BC3_UNorm => return Some(vk::Format::BC3_SRGB_BLOCK),
-            DxgiFormat::BC3_UNorm_sRGB => return Some(vk::Format::BC3_SRGB_BLOCK),
-            DxgiFormat::BC4_UNorm => return Some(vk::Format::BC4_UNORM_BLOCK),
-            DxgiFormat::BC4_SNorm => return Some(vk::Format::BC4_SNORM_BLOCK),
-            DxgiFormat::BC5_UNorm => return Some(vk::Format::BC5_UNORM_BLOCK),
-            DxgiFormat::BC5_SNorm => return Some(vk::Format::BC5_SNORM_BLOCK),
-            DxgiFormat::BC6H_SF16 => return Some(vk::Format::BC6H_SFLOAT_BLOCK),
-            DxgiFormat::BC6H_UF16 => return Some(vk::Format::BC6H_UFLOAT_BLOCK),
-            DxgiFormat::BC7_UNorm => return Some(vk::Format::BC7_UNORM_BLOCK),
-            DxgiFormat::BC7_UNorm_sRGB => return Some(vk::Format::BC7_UNORM_BLOCK),
-            _ => return None,
-        }
-    }
-
-    None
-}
-
-pub fn process_image(content: ImageContent) -> Result<ImageAsset, Error> {
-    match &content.data {
-        RawImageData::Dds(dds) => process_dds(dds),
-        RawImageData::Rgba(image) => process_rgba(image, content.desc),
+pub fn process_image(
+    content: ImageContent,
+    default_compression: ImageCompression,
+) -> Result<ImageAsset, Error> {
+    let compression = content.desc.compression.unwrap_or(default_compression);
+    match content.data {
+        RawImageData::Dds(dds) => process_dds(&dds, content.desc.srgb),
+        RawImageData::Ktx2(image) => Ok(image),
+        RawImageData::Rgba(image) => process_rgba(&image, content.desc, compression, usize::MAX),
     }
 }
 
@@ -233,38 +325,56 @@ fn is_pow2(x: u32) -> bool {
     x & (x - 1) == 0
 }
 
-mod dds_util {
-    pub fn get_texture_size(pitch: u32, pitch_height: u32, height: u32, depth: u32) -> usize {
-        let row_height = (height + (pitch_height - 1)) / pitch_height;
-        pitch as usize * row_height as usize * depth as usize
+impl AssetImporter for ImageSource {
+    fn import(&self, ctx: &dyn ImportContext) -> Result<Arc<dyn Asset>, Error> {
+        let content = import_image(self)?;
+        Ok(Arc::new(process_image(
+            content,
+            ctx.settings().compression,
+        )?))
     }
 
-    pub fn get_pitch(dds: &ddsfile::Dds, width: u32) -> Option<u32> {
-        // Try format first
-        if let Some(format) = dds.get_format() {
-            if let Some(pitch) = format.get_pitch(width) {
-                return Some(pitch);
-            }
+    fn is_changed(&self, timestamp: std::time::SystemTime) -> bool {
+        match &self.source {
+            ImageDataSource::File(path) => is_asset_changed(path, timestamp),
+            _ => false,
         }
+    }
 
-        // Then try to calculate it ourselves
-        if let Some(bpp) = dds.get_bits_per_pixel() {
-            return Some((bpp * width + 7) / 8);
-        }
-        None
+    fn resolve(&self, settings: &ImportSettings) -> Option<Box<dyn AssetImporter>> {
+        self.desc.compression.is_none().then(|| {
+            Box::new(Self {
+                source: self.source.clone(),
+                desc: self.desc.compression(settings.compression),
+            }) as _
+        })
     }
 }
 
-impl AssetImporter for ImageSource {
-    fn import(&self, _ctx: &dyn ImportContext) -> Result<Arc<dyn Asset>, Error> {
-        let content = import_image(self)?;
-        Ok(Arc::new(process_image(content)?))
+impl AssetImporter for PackedImageSource {
+    fn import(&self, ctx: &dyn ImportContext) -> Result<Arc<dyn Asset>, Error> {
+        let content = import_packed_image(self)?;
+        Ok(Arc::new(process_image(
+            content,
+            ctx.settings().compression,
+        )?))
     }
 
     fn is_changed(&self, timestamp: std::time::SystemTime) -> bool {
-        match &self.source {
-            ImageDataSource::File(path) => is_asset_changed(path, timestamp),
+        self.channels.iter().any(|channel| match channel {
+            ChannelSource::Image(ImageDataSource::File(path), _) => {
+                is_asset_changed(path, timestamp)
+            }
             _ => false,
-        }
+        })
+    }
+
+    fn resolve(&self, settings: &ImportSettings) -> Option<Box<dyn AssetImporter>> {
+        self.desc.compression.is_none().then(|| {
+            Box::new(Self {
+                channels: self.channels.clone(),
+                desc: self.desc.compression(settings.compression),
+            }) as _
+        })
     }
 }
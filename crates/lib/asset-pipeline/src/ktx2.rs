@@ -0,0 +1,450 @@
+//! Minimal KTX2 container support.
+//!
+//! Only non-supercompressed files are supported, data is stored as is.
+
+use std::io::Write;
+
+use ash::vk;
+use dess_assets::ImageAsset;
+use dess_common::Align;
+
+use crate::Error;
+
+const IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+const HEADER_SIZE: usize = 80;
+const LEVEL_INDEX_ENTRY_SIZE: usize = 24;
+
+const MODEL_RGBSDA: u8 = 1;
+const MODEL_BC1A: u8 = 128;
+const MODEL_BC2: u8 = 129;
+const MODEL_BC3: u8 = 130;
+const MODEL_BC4: u8 = 131;
+const MODEL_BC5: u8 = 132;
+const MODEL_BC6H: u8 = 133;
+const MODEL_BC7: u8 = 134;
+
+const PRIMARIES_BT709: u8 = 1;
+const TRANSFER_LINEAR: u8 = 1;
+const TRANSFER_SRGB: u8 = 2;
+
+const CHANNEL_RED: u8 = 0;
+const CHANNEL_GREEN: u8 = 1;
+const CHANNEL_BLUE: u8 = 2;
+const CHANNEL_ALPHA: u8 = 15;
+const CHANNEL_BC1A_ALPHA_PRESENT: u8 = 1;
+
+const SAMPLE_LINEAR: u8 = 1 << 4;
+const SAMPLE_SIGNED: u8 = 1 << 6;
+const SAMPLE_FLOAT: u8 = 1 << 7;
+
+pub fn is_ktx2(data: &[u8]) -> bool {
+    data.starts_with(&IDENTIFIER)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    channel: u8,
+    offset: u16,
+    length: u8,
+    lower: u32,
+    upper: u32,
+}
+
+impl Sample {
+    fn unorm(channel: u8, offset: u16, length: u8) -> Self {
+        let upper = if length >= 32 {
+            u32::MAX
+        } else {
+            (1 << length) - 1
+        };
+        Self {
+            channel,
+            offset,
+            length,
+            lower: 0,
+            upper,
+        }
+    }
+
+    fn snorm(channel: u8, offset: u16, length: u8) -> Self {
+        Self {
+            channel: channel | SAMPLE_SIGNED,
+            offset,
+            length,
+            lower: i32::MIN as u32,
+            upper: i32::MAX as u32,
+        }
+    }
+
+    fn float(channel: u8, offset: u16, length: u8, signed: bool) -> Self {
+        Self {
+            channel: channel | SAMPLE_FLOAT | if signed { SAMPLE_SIGNED } else { 0 },
+            offset,
+            length,
+            lower: if signed { (-1.0f32).to_bits() } else { 0 },
+            upper: 1.0f32.to_bits(),
+        }
+    }
+}
+
+struct FormatDesc {
+    model: u8,
+    block: [u8; 2],
+    bytes: u8,
+    samples: Vec<Sample>,
+}
+
+fn format_desc(format: vk::Format) -> Option<FormatDesc> {
+    let bc = |model, bytes, samples| FormatDesc {
+        model,
+        block: [4, 4],
+        bytes,
+        samples,
+    };
+    let desc = match format {
+        vk::Format::R8G8B8A8_UNORM | vk::Format::R8G8B8A8_SRGB => FormatDesc {
+            model: MODEL_RGBSDA,
+            block: [1, 1],
+            bytes: 4,
+            samples: vec![
+                Sample::unorm(CHANNEL_RED, 0, 8),
+                Sample::unorm(CHANNEL_GREEN, 8, 8),
+                Sample::unorm(CHANNEL_BLUE, 16, 8),
+                Sample::unorm(CHANNEL_ALPHA, 24, 8),
+            ],
+        },
+        vk::Format::BC1_RGB_UNORM_BLOCK | vk::Format::BC1_RGB_SRGB_BLOCK => {
+            bc(MODEL_BC1A, 8, vec![Sample::unorm(CHANNEL_RED, 0, 64)])
+        }
+        vk::Format::BC1_RGBA_UNORM_BLOCK | vk::Format::BC1_RGBA_SRGB_BLOCK => bc(
+            MODEL_BC1A,
+            8,
+            vec![Sample::unorm(CHANNEL_BC1A_ALPHA_PRESENT, 0, 64)],
+        ),
+        vk::Format::BC2_UNORM_BLOCK | vk::Format::BC2_SRGB_BLOCK => bc(
+            MODEL_BC2,
+            16,
+            vec![
+                Sample::unorm(CHANNEL_ALPHA, 0, 64),
+                Sample::unorm(CHANNEL_RED, 64, 64),
+            ],
+        ),
+        vk::Format::BC3_UNORM_BLOCK | vk::Format::BC3_SRGB_BLOCK => bc(
+            MODEL_BC3,
+            16,
+            vec![
+                Sample::unorm(CHANNEL_ALPHA, 0, 64),
+                Sample::unorm(CHANNEL_RED, 64, 64),
+            ],
+        ),
+        vk::Format::BC4_UNORM_BLOCK => bc(MODEL_BC4, 8, vec![Sample::unorm(CHANNEL_RED, 0, 64)]),
+        vk::Format::BC4_SNORM_BLOCK => bc(MODEL_BC4, 8, vec![Sample::snorm(CHANNEL_RED, 0, 64)]),
+        vk::Format::BC5_UNORM_BLOCK => bc(
+            MODEL_BC5,
+            16,
+            vec![
+                Sample::unorm(CHANNEL_RED, 0, 64),
+                Sample::unorm(CHANNEL_GREEN, 64, 64),
+            ],
+        ),
+        vk::Format::BC5_SNORM_BLOCK => bc(
+            MODEL_BC5,
+            16,
+            vec![
+                Sample::snorm(CHANNEL_RED, 0, 64),
+                Sample::snorm(CHANNEL_GREEN, 64, 64),
+            ],
+        ),
+        vk::Format::BC6H_UFLOAT_BLOCK => bc(
+            MODEL_BC6H,
+            16,
+            vec![Sample::float(CHANNEL_RED, 0, 128, false)],
+        ),
+        vk::Format::BC6H_SFLOAT_BLOCK => bc(
+            MODEL_BC6H,
+            16,
+            vec![Sample::float(CHANNEL_RED, 0, 128, true)],
+        ),
+        vk::Format::BC7_UNORM_BLOCK | vk::Format::BC7_SRGB_BLOCK => {
+            bc(MODEL_BC7, 16, vec![Sample::unorm(CHANNEL_RED, 0, 128)])
+        }
+        _ => return None,
+    };
+
+    Some(desc)
+}
+
+fn is_srgb(format: vk::Format) -> bool {
+    matches!(
+        format,
+        vk::Format::R8G8B8A8_SRGB
+            | vk::Format::BC1_RGB_SRGB_BLOCK
+            | vk::Format::BC1_RGBA_SRGB_BLOCK
+            | vk::Format::BC2_SRGB_BLOCK
+            | vk::Format::BC3_SRGB_BLOCK
+            | vk::Format::BC7_SRGB_BLOCK
+    )
+}
+
+fn data_format_descriptor(format: vk::Format, desc: &FormatDesc) -> Vec<u8> {
+    let srgb = is_srgb(format);
+    let block_size = 24 + 16 * desc.samples.len() as u32;
+    let mut result = Vec::with_capacity(4 + block_size as usize);
+    result.extend_from_slice(&(4 + block_size).to_le_bytes());
+    // Vendor id and descriptor type are both zero for basic descriptor.
+    result.extend_from_slice(&0u32.to_le_bytes());
+    result.extend_from_slice(&2u16.to_le_bytes());
+    result.extend_from_slice(&(block_size as u16).to_le_bytes());
+    result.extend_from_slice(&[
+        desc.model,
+        PRIMARIES_BT709,
+        if srgb { TRANSFER_SRGB } else { TRANSFER_LINEAR },
+        0,
+    ]);
+    result.extend_from_slice(&[desc.block[0] - 1, desc.block[1] - 1, 0, 0]);
+    result.extend_from_slice(&[desc.bytes, 0, 0, 0, 0, 0, 0, 0]);
+    for sample in &desc.samples {
+        let mut channel = sample.channel;
+        if srgb && channel & 0xF == CHANNEL_ALPHA {
+            channel |= SAMPLE_LINEAR;
+        }
+        result.extend_from_slice(&sample.offset.to_le_bytes());
+        result.extend_from_slice(&[sample.length - 1, channel]);
+        result.extend_from_slice(&[0, 0, 0, 0]);
+        result.extend_from_slice(&sample.lower.to_le_bytes());
+        result.extend_from_slice(&sample.upper.to_le_bytes());
+    }
+
+    result
+}
+
+/// Write cooked image as KTX2 container.
+pub fn write_ktx2<W: Write>(image: &ImageAsset, writer: &mut W) -> Result<(), Error> {
+    let desc = format_desc(image.format).ok_or(Error::ProcessingFailed(format!(
+        "Format {:?} can't be exported to KTX2",
+        image.format
+    )))?;
+    let dfd = data_format_descriptor(image.format, &desc);
+    let level_count = image.mips.len();
+    let dfd_offset = HEADER_SIZE + LEVEL_INDEX_ENTRY_SIZE * level_count;
+    // Levels must be aligned to lcm(texel block size, 4), all supported
+    // formats have block size that is either 4 or multiple of 4.
+    let alignment = (desc.bytes as usize).max(4);
+
+    // Smallest mip goes first.
+    let mut offsets = vec![0; level_count];
+    let mut cursor = dfd_offset + dfd.len();
+    for level in (0..level_count).rev() {
+        cursor = cursor.align(alignment);
+        offsets[level] = cursor;
+        cursor += image.mips[level].len();
+    }
+
+    let faces = if image.cubemap { 6 } else { 1 };
+    let layers = image.layers / faces;
+    let mut header = Vec::with_capacity(dfd_offset);
+    header.extend_from_slice(&IDENTIFIER);
+    for value in [
+        image.format.as_raw() as u32,
+        1,
+        image.dimensions[0],
+        image.dimensions[1],
+        0,
+        if layers > 1 { layers } else { 0 },
+        faces,
+        level_count as u32,
+        0,
+        dfd_offset as u32,
+        dfd.len() as u32,
+        0,
+        0,
+    ] {
+        header.extend_from_slice(&value.to_le_bytes());
+    }
+    header.extend_from_slice(&0u64.to_le_bytes());
+    header.extend_from_slice(&0u64.to_le_bytes());
+    for (level, mip) in image.mips.iter().enumerate() {
+        header.extend_from_slice(&(offsets[level] as u64).to_le_bytes());
+        header.extend_from_slice(&(mip.len() as u64).to_le_bytes());
+        header.extend_from_slice(&(mip.len() as u64).to_le_bytes());
+    }
+
+    writer.write_all(&header)?;
+    writer.write_all(&dfd)?;
+    let mut cursor = dfd_offset + dfd.len();
+    for level in (0..level_count).rev() {
+        let padding = offsets[level] - cursor;
+        writer.write_all(&vec![0u8; padding])?;
+        writer.write_all(&image.mips[level])?;
+        cursor = offsets[level] + image.mips[level].len();
+    }
+
+    Ok(())
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    cursor: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], Error> {
+        if self.cursor + count > self.data.len() {
+            return Err(Error::ProcessingFailed(
+                "Unexpected end of KTX2 file".into(),
+            ));
+        }
+        let result = &self.data[self.cursor..self.cursor + count];
+        self.cursor += count;
+        Ok(result)
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, Error> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+}
+
+/// Read KTX2 container into cooked image.
+pub fn read_ktx2(data: &[u8]) -> Result<ImageAsset, Error> {
+    if !is_ktx2(data) {
+        return Err(Error::ProcessingFailed("Not a KTX2 file".into()));
+    }
+    let mut reader = Reader {
+        data,
+        cursor: IDENTIFIER.len(),
+    };
+    let format = vk::Format::from_raw(reader.u32()? as i32);
+    let _type_size = reader.u32()?;
+    let width = reader.u32()?;
+    let height = reader.u32()?;
+    let depth = reader.u32()?;
+    let layers = reader.u32()?;
+    let faces = reader.u32()?;
+    let levels = reader.u32()?.max(1);
+    let supercompression = reader.u32()?;
+    if format == vk::Format::UNDEFINED {
+        return Err(Error::ProcessingFailed(
+            "KTX2 files without Vulkan format aren't supported".into(),
+        ));
+    }
+    if supercompression != 0 {
+        return Err(Error::ProcessingFailed(format!(
+            "KTX2 supercompression scheme {} isn't supported",
+            supercompression
+        )));
+    }
+    if height == 0 || depth > 1 {
+        return Err(Error::ProcessingFailed(
+            "Only 2D KTX2 textures are supported".into(),
+        ));
+    }
+    if faces != 1 && faces != 6 {
+        return Err(Error::ProcessingFailed(format!(
+            "Invalid KTX2 face count {}",
+            faces
+        )));
+    }
+    // Skip DFD, KVD and SGD indices.
+    reader.bytes(4 * 4 + 2 * 8)?;
+    let mut mips = Vec::with_capacity(levels as usize);
+    for _ in 0..levels {
+        let offset = reader.u64()?;
+        let length = reader.u64()?;
+        let _uncompressed_length = reader.u64()?;
+        let mip = offset
+            .checked_add(length)
+            .and_then(|end| data.get(usize::try_from(offset).ok()?..usize::try_from(end).ok()?))
+            .ok_or(Error::ProcessingFailed("KTX2 level is out of file".into()))?;
+        mips.push(mip.to_vec());
+    }
+
+    Ok(ImageAsset {
+        format,
+        dimensions: [width, height],
+        layers: layers.max(1) * faces,
+        cubemap: faces == 6,
+        mips,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use ash::vk;
+    use dess_assets::ImageAsset;
+
+    use super::{read_ktx2, write_ktx2, HEADER_SIZE};
+
+    #[test]
+    fn roundtrip() {
+        let image = ImageAsset {
+            format: vk::Format::BC1_RGB_SRGB_BLOCK,
+            dimensions: [8, 8],
+            layers: 1,
+            cubemap: false,
+            mips: vec![vec![1u8; 32], vec![2u8; 8]],
+        };
+        let mut data = Vec::new();
+        write_ktx2(&image, &mut data).unwrap();
+        let result = read_ktx2(&data).unwrap();
+        assert_eq!(image.format, result.format);
+        assert_eq!(image.dimensions, result.dimensions);
+        assert_eq!(image.layers, result.layers);
+        assert_eq!(image.mips, result.mips);
+    }
+
+    #[test]
+    fn cubemap_roundtrip() {
+        let image = ImageAsset {
+            format: vk::Format::R8G8B8A8_UNORM,
+            dimensions: [1, 1],
+            layers: 12,
+            cubemap: true,
+            mips: vec![(0..48).collect()],
+        };
+        let mut data = Vec::new();
+        write_ktx2(&image, &mut data).unwrap();
+        let result = read_ktx2(&data).unwrap();
+        assert_eq!(12, result.layers);
+        assert!(result.cubemap);
+        assert_eq!(image.mips, result.mips);
+    }
+
+    #[test]
+    fn level_out_of_file_is_rejected() {
+        let image = ImageAsset {
+            format: vk::Format::R8G8B8A8_UNORM,
+            dimensions: [1, 1],
+            layers: 1,
+            cubemap: false,
+            mips: vec![vec![0u8; 4]],
+        };
+        let mut data = Vec::new();
+        write_ktx2(&image, &mut data).unwrap();
+        // Offset and length of first level overflow when added
+        data[HEADER_SIZE..HEADER_SIZE + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(read_ktx2(&data).is_err());
+        let end = data.len() as u64;
+        data[HEADER_SIZE..HEADER_SIZE + 8].copy_from_slice(&end.to_le_bytes());
+        assert!(read_ktx2(&data).is_err());
+    }
+
+    #[test]
+    fn levels_are_aligned() {
+        let image = ImageAsset {
+            format: vk::Format::BC7_UNORM_BLOCK,
+            dimensions: [4, 4],
+            layers: 1,
+            cubemap: false,
+            mips: vec![vec![3u8; 16]],
+        };
+        let mut data = Vec::new();
+        write_ktx2(&image, &mut data).unwrap();
+        assert_eq!(0, (data.len() - 16) % 16);
+    }
+}
@@ -1,5 +1,11 @@
+mod animation;
+mod atlas;
+mod bounds;
+mod dds;
 mod gltf;
 mod image;
+mod ktx2;
+mod validation;
 
 use std::{
     collections::{HashMap, HashSet},
@@ -13,11 +19,16 @@ use std::{
 
 use ::image::ImageError;
 use bevy_tasks::AsyncComputeTaskPool;
-use dess_assets::{get_absolute_asset_path, get_cached_asset_path, Asset, AssetRef, ContentSource};
+use dess_assets::{
+    get_absolute_asset_path, get_cached_asset_path, Asset, AssetRef, ContentSource,
+    ImageCompression,
+};
 pub use gltf::*;
 pub use image::*;
+pub use ktx2::*;
 use log::info;
 use parking_lot::Mutex;
+pub use validation::MeshReport;
 
 #[derive(Debug)]
 pub enum Error {
@@ -40,10 +51,35 @@ impl From<ImageError> for Error {
 pub trait AssetImporter: ContentSource {
     fn import(&self, ctx: &dyn ImportContext) -> Result<Arc<dyn Asset>, Error>;
     fn is_changed(&self, timestamp: SystemTime) -> bool;
+
+    /// Copy of source with project-wide defaults filled in, so they become
+    /// part of asset identity. None if source doesn't depend on settings.
+    fn resolve(&self, _settings: &ImportSettings) -> Option<Box<dyn AssetImporter>> {
+        None
+    }
 }
 
 pub trait ImportContext {
     fn import(&self, content: Box<dyn AssetImporter>) -> AssetRef;
+    fn settings(&self) -> &ImportSettings;
+    /// Problems found in source geometry, imported anyway.
+    fn report_mesh(&self, mesh: String, report: MeshReport);
+}
+
+/// Project-wide import settings.
+///
+/// Used by importers when source doesn't override them.
+#[derive(Debug, Clone, Copy)]
+pub struct ImportSettings {
+    pub compression: ImageCompression,
+}
+
+impl Default for ImportSettings {
+    fn default() -> Self {
+        Self {
+            compression: ImageCompression::Basic,
+        }
+    }
 }
 
 pub(crate) fn read_to_end<P>(path: P) -> io::Result<Vec<u8>>
@@ -63,12 +99,15 @@ where
 
 #[derive(Debug, Default)]
 pub struct ContentProcessor {
+    settings: ImportSettings,
     to_process: Mutex<HashMap<AssetRef, Box<dyn AssetImporter>>>,
     processed: Mutex<HashSet<AssetRef>>,
+    mesh_reports: Mutex<Vec<(String, MeshReport)>>,
 }
 
 impl ImportContext for ContentProcessor {
     fn import(&self, content: Box<dyn AssetImporter>) -> AssetRef {
+        let content = content.resolve(&self.settings).unwrap_or(content);
         let asset = content.get_ref();
         if self.processed.lock().contains(&asset) {
             return asset;
@@ -80,9 +119,24 @@ impl ImportContext for ContentProcessor {
         to_process.insert(asset, content);
         asset
     }
+
+    fn settings(&self) -> &ImportSettings {
+        &self.settings
+    }
+
+    fn report_mesh(&self, mesh: String, report: MeshReport) {
+        self.mesh_reports.lock().push((mesh, report));
+    }
 }
 
 impl ContentProcessor {
+    pub fn new(settings: ImportSettings) -> Self {
+        Self {
+            settings,
+            ..Default::default()
+        }
+    }
+
     pub fn process(&self) {
         loop {
             let mut to_process = self
@@ -104,6 +158,12 @@ impl ContentProcessor {
         }
     }
 
+    /// Meshes with broken geometry found by `process`, keyed by source path
+    /// and mesh name.
+    pub fn mesh_reports(&self) -> Vec<(String, MeshReport)> {
+        self.mesh_reports.lock().clone()
+    }
+
     pub fn need_rebuild(content: &dyn AssetImporter) -> bool {
         let path = get_cached_asset_path(content.get_ref());
         if path.exists() {
@@ -151,3 +211,30 @@ pub(crate) fn is_asset_changed<P: AsRef<Path>>(path: P, timestamp: SystemTime) -
     }
     false
 }
+
+#[cfg(test)]
+mod test {
+    use dess_assets::{ImageCompression, ImageSource, ImageSourceDesc};
+
+    use crate::{ContentProcessor, ImportContext, ImportSettings};
+
+    #[test]
+    fn default_compression_is_part_of_identity() {
+        let import = |compression| {
+            ContentProcessor::new(ImportSettings { compression }).import(Box::new(
+                ImageSource::from_color([1.0; 4], ImageSourceDesc::color()),
+            ))
+        };
+        assert_ne!(
+            import(ImageCompression::Fast),
+            import(ImageCompression::Slow)
+        );
+        assert_eq!(
+            import(ImageCompression::Fast),
+            ContentProcessor::default().import(Box::new(ImageSource::from_color(
+                [1.0; 4],
+                ImageSourceDesc::color().compression(ImageCompression::Fast)
+            )))
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use std::{collections::HashSet, fmt::Display};
+
+use dess_assets::MeshRepair;
+
+/// Problems found in source geometry of single mesh.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MeshReport {
+    /// NaN or infinite vertex components.
+    pub non_finite_values: usize,
+    pub degenerate_triangles: usize,
+    /// Triangles referencing vertices that don't exist.
+    pub invalid_triangles: usize,
+    pub unnormalized_vectors: usize,
+    /// Negative zeroes prevent welding of otherwise equal vertices.
+    pub negative_zeroes: usize,
+    /// Vertices equal to another vertex in every attribute, they're welded.
+    pub duplicate_vertices: usize,
+}
+
+impl MeshReport {
+    pub fn is_clean(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Display for MeshReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} non-finite values, {} degenerate triangles, {} invalid triangles, {} unnormalized vectors, {} negative zeroes, {} duplicate vertices",
+            self.non_finite_values,
+            self.degenerate_triangles,
+            self.invalid_triangles,
+            self.unnormalized_vectors,
+            self.negative_zeroes,
+            self.duplicate_vertices
+        )
+    }
+}
+
+/// Replaces NaN and infinite values with zeroes if allowed, negative zeroes
+/// are always replaced, so vertex remap can merge such vertices.
+pub(crate) fn sanitize_values<const N: usize>(
+    values: &mut [[f32; N]],
+    repair: MeshRepair,
+    report: &mut MeshReport,
+) {
+    for value in values.iter_mut().flatten() {
+        if !value.is_finite() {
+            report.non_finite_values += 1;
+            if repair.clamp {
+                *value = 0.0;
+            }
+        } else if *value == 0.0 && value.is_sign_negative() {
+            report.negative_zeroes += 1;
+            *value = 0.0;
+        }
+    }
+}
+
+/// Checks that first three components form unit vector. Fourth component of
+/// tangents is handedness and must be either 1 or -1.
+pub(crate) fn normalize_vectors<const N: usize>(
+    values: &mut [[f32; N]],
+    repair: MeshRepair,
+    report: &mut MeshReport,
+) {
+    for value in values {
+        let vector = glam::Vec3::new(value[0], value[1], value[2]);
+        let unnormalized = (vector.length() - 1.0).abs() > 1e-3;
+        let bad_sign = N > 3 && value[3].abs() != 1.0;
+        if !unnormalized && !bad_sign {
+            continue;
+        }
+        report.unnormalized_vectors += 1;
+        if repair.renormalize {
+            let vector = vector.try_normalize().unwrap_or(glam::Vec3::Z);
+            value[..3].copy_from_slice(&vector.to_array());
+            if N > 3 {
+                value[3] = if value[3] < 0.0 { -1.0 } else { 1.0 };
+            }
+        }
+    }
+}
+
+/// Counts vertices that repeat earlier one in all streams. Streams hold
+/// bytes of every vertex, empty ones are skipped.
+pub(crate) fn count_duplicate_vertices(
+    streams: &[&[u8]],
+    vertex_count: usize,
+    report: &mut MeshReport,
+) {
+    let streams = streams
+        .iter()
+        .filter(|stream| !stream.is_empty())
+        .map(|stream| (*stream, stream.len() / vertex_count.max(1)))
+        .collect::<Vec<_>>();
+    let mut unique = HashSet::with_capacity(vertex_count);
+    for vertex in 0..vertex_count {
+        let key = streams
+            .iter()
+            .flat_map(|(stream, stride)| &stream[vertex * stride..(vertex + 1) * stride])
+            .copied()
+            .collect::<Vec<_>>();
+        if !unique.insert(key) {
+            report.duplicate_vertices += 1;
+        }
+    }
+}
+
+/// Reports triangles with zero area and removes them if allowed, triangles
+/// with out of range indices are always removed. Area threshold is relative to mesh extent, so it doesn't
+/// depend on source units.
+pub(crate) fn validate_triangles(
+    indices: &mut Vec<u32>,
+    positions: &[[f32; 3]],
+    repair: MeshRepair,
+    report: &mut MeshReport,
+) {
+    let (min, max) = positions
+        .iter()
+        .map(|x| glam::Vec3::from(*x))
+        .filter(|x| x.is_finite())
+        .fold(
+            (glam::Vec3::splat(f32::MAX), glam::Vec3::splat(f32::MIN)),
+            |(min, max), x| (min.min(x), max.max(x)),
+        );
+    // Squared length of cross product is compared, it's fourth power of size
+    let threshold = (max - min).max(glam::Vec3::ZERO).length_squared() * f32::EPSILON;
+    let threshold = threshold * threshold;
+    let mut result = Vec::with_capacity(indices.len());
+    for triangle in indices.chunks_exact(3) {
+        if triangle.iter().any(|x| *x as usize >= positions.len()) {
+            report.invalid_triangles += 1;
+            continue;
+        }
+        let a = glam::Vec3::from(positions[triangle[0] as usize]);
+        let b = glam::Vec3::from(positions[triangle[1] as usize]);
+        let c = glam::Vec3::from(positions[triangle[2] as usize]);
+        let area = (b - a).cross(c - a).length_squared();
+        if area.is_nan() || area <= threshold {
+            report.degenerate_triangles += 1;
+            if repair.drop_degenerate {
+                continue;
+            }
+        }
+        result.extend_from_slice(triangle);
+    }
+    *indices = result;
+}
+
+#[cfg(test)]
+mod test {
+    use dess_assets::MeshRepair;
+
+    use super::{
+        count_duplicate_vertices, normalize_vectors, sanitize_values, validate_triangles,
+        MeshReport,
+    };
+
+    #[test]
+    fn broken_geometry_is_repaired() {
+        let mut report = MeshReport::default();
+        let mut positions = [
+            [0.0, 0.0, -0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, f32::NAN, 0.0],
+            [0.0, 1.0, 0.0],
+        ];
+        sanitize_values(&mut positions, MeshRepair::default(), &mut report);
+        assert_eq!(report.non_finite_values, 1);
+        assert_eq!(report.negative_zeroes, 1);
+        assert!(positions[0][2].is_sign_positive());
+
+        let mut indices = vec![0, 1, 3, 0, 1, 2, 0, 1, 7];
+        validate_triangles(&mut indices, &positions, MeshRepair::default(), &mut report);
+        assert_eq!(indices, [0, 1, 3]);
+        assert_eq!(report.degenerate_triangles, 1);
+        assert_eq!(report.invalid_triangles, 1);
+
+        let mut tangents = [[2.0, 0.0, 0.0, 0.5], [0.0, 1.0, 0.0, -1.0]];
+        normalize_vectors(&mut tangents, MeshRepair::default(), &mut report);
+        assert_eq!(tangents, [[1.0, 0.0, 0.0, 1.0], [0.0, 1.0, 0.0, -1.0]]);
+        assert_eq!(report.unnormalized_vectors, 1);
+
+        let positions = [0u8; 9];
+        let colors = [0, 0, 1, 0, 0, 0];
+        count_duplicate_vertices(&[&positions, &colors, &[]], 3, &mut report);
+        assert_eq!(report.duplicate_vertices, 1);
+    }
+
+    #[test]
+    fn degenerate_threshold_is_relative() {
+        // Same sliver triangle in millimeters and in kilometers
+        for scale in [1e-3, 1e3] {
+            let mut report = MeshReport::default();
+            let positions = [
+                [0.0, 0.0, 0.0],
+                [scale, 0.0, 0.0],
+                [0.0, scale * 1e-3, 0.0],
+                [0.0, 0.0, scale * 1e-9],
+            ];
+            let mut indices = vec![0, 1, 2, 0, 1, 3];
+            validate_triangles(&mut indices, &positions, MeshRepair::default(), &mut report);
+            assert_eq!(indices, [0, 1, 2]);
+            assert_eq!(report.degenerate_triangles, 1);
+        }
+    }
+
+    #[test]
+    fn problems_are_kept_without_repair() {
+        let repair = MeshRepair {
+            drop_degenerate: false,
+            renormalize: false,
+            clamp: false,
+        };
+        let mut report = MeshReport::default();
+        let positions = [[0.0; 3]; 3];
+        let mut indices = vec![0, 1, 2];
+        validate_triangles(&mut indices, &positions, repair, &mut report);
+        assert_eq!(indices, [0, 1, 2]);
+        assert_eq!(report.degenerate_triangles, 1);
+        assert!(!report.is_clean());
+    }
+}
@@ -0,0 +1,80 @@
+// Copyright (C) 2023 Vladimir Kuskov
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use bytes::Bytes;
+use speedy::{Readable, Writable};
+
+use crate::{Asset, AssetLoad};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Readable, Writable)]
+pub enum AnimationInterpolation {
+    Step,
+    Linear,
+    /// Each key has in tangent, value and out tangent.
+    CubicSpline,
+}
+
+#[derive(Debug, Clone, PartialEq, Readable, Writable)]
+pub enum AnimationValues {
+    Translation(Vec<[f32; 3]>),
+    /// Quaternions quantized to signed 16 bit normalized values. Tangents of
+    /// cubic spline can be out of unit range, so values are scaled by range.
+    Rotation {
+        range: f32,
+        values: Vec<[i16; 4]>,
+    },
+    Scale(Vec<[f32; 3]>),
+    /// Weights of all morph targets for each key.
+    MorphWeights(Vec<f32>),
+}
+
+impl AnimationValues {
+    pub fn rotation(&self, index: usize) -> Option<[f32; 4]> {
+        if let Self::Rotation { range, values } = self {
+            values
+                .get(index)
+                .map(|x| x.map(|x| x as f32 / i16::MAX as f32 * range))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Readable, Writable)]
+pub struct AnimationChannel {
+    /// Bone path, same as in `ModelAsset::bone_names`.
+    pub bone: String,
+    pub interpolation: AnimationInterpolation,
+    pub times: Vec<f32>,
+    pub values: AnimationValues,
+}
+
+#[derive(Debug, Default, Readable, Writable)]
+pub struct AnimationClipAsset {
+    pub duration: f32,
+    pub channels: Vec<AnimationChannel>,
+}
+
+impl Asset for AnimationClipAsset {
+    fn to_bytes(&self) -> std::io::Result<Bytes> {
+        Ok(self.write_to_vec()?.into())
+    }
+}
+
+impl AssetLoad for AnimationClipAsset {
+    fn from_bytes(data: &[u8]) -> std::io::Result<Self> {
+        Ok(Self::read_from_buffer(data)?)
+    }
+}
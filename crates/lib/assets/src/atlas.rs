@@ -0,0 +1,126 @@
+// Copyright (C) 2023 Vladimir Kuskov
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{collections::HashMap, hash::Hash, path::Path};
+
+use bytes::Bytes;
+use siphasher::sip128::Hasher128;
+use speedy::{Readable, Writable};
+
+use crate::{Asset, AssetLoad, AssetRef, ContentSource, ImageAsset, ImageSourceDesc};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Readable, Writable)]
+pub enum AtlasInput {
+    /// Every image in directory, sprite names are file stems.
+    Directory(String),
+    /// Explicit list of images, sprite names are file stems.
+    Files(Vec<String>),
+}
+
+/// Many small images packed into single texture.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Readable, Writable)]
+pub struct AtlasSource {
+    pub input: AtlasInput,
+    /// Empty space between sprites in pixels.
+    pub padding: u32,
+    /// How many pixels of sprite border are repeated outside of it, prevents
+    /// bleeding with linear filtering. Also limits mips, mip `n` is built only
+    /// when extrude is at least `2^n`.
+    pub extrude: u32,
+    pub desc: ImageSourceDesc,
+}
+
+impl AtlasSource {
+    pub fn from_directory<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            input: AtlasInput::Directory(path.as_ref().to_str().unwrap().to_owned()),
+            padding: 2,
+            extrude: 1,
+            desc: ImageSourceDesc::color(),
+        }
+    }
+
+    pub fn from_files<P: AsRef<Path>>(paths: &[P]) -> Self {
+        Self {
+            input: AtlasInput::Files(
+                paths
+                    .iter()
+                    .map(|path| path.as_ref().to_str().unwrap().to_owned())
+                    .collect(),
+            ),
+            padding: 2,
+            extrude: 1,
+            desc: ImageSourceDesc::color(),
+        }
+    }
+
+    pub fn padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+
+        self
+    }
+
+    pub fn extrude(mut self, extrude: u32) -> Self {
+        self.extrude = extrude;
+
+        self
+    }
+
+    pub fn desc(mut self, desc: ImageSourceDesc) -> Self {
+        self.desc = desc;
+
+        self
+    }
+}
+
+impl ContentSource for AtlasSource {
+    fn get_ref(&self) -> AssetRef {
+        let mut hasher = siphasher::sip128::SipHasher::default();
+        self.hash(&mut hasher);
+        hasher.finish128().as_u128().into()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Readable, Writable)]
+pub struct AtlasSprite {
+    /// Position and size in pixels: x, y, width, height.
+    pub rect: [u32; 4],
+    /// Normalized texture coordinates: min u, min v, max u, max v.
+    pub uv: [f32; 4],
+}
+
+#[derive(Debug, Default, Readable, Writable)]
+pub struct AtlasAsset {
+    pub image: ImageAsset,
+    pub sprites: HashMap<String, AtlasSprite>,
+}
+
+impl AtlasAsset {
+    pub fn sprite(&self, name: &str) -> Option<&AtlasSprite> {
+        self.sprites.get(name)
+    }
+}
+
+impl Asset for AtlasAsset {
+    fn to_bytes(&self) -> std::io::Result<Bytes> {
+        Ok(self.write_to_vec()?.into())
+    }
+}
+
+impl AssetLoad for AtlasAsset {
+    fn from_bytes(data: &[u8]) -> std::io::Result<Self> {
+        Ok(Self::read_from_buffer(data)?)
+    }
+}
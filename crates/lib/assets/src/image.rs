@@ -28,11 +28,29 @@ pub enum ImageType {
     Rg,
 }
 
+/// Block compression preset.
+///
+/// Quality presets pick BC7 for color data and BC5 for two-channel data,
+/// trading build time for quality. `Bc1` and `Bc3` force legacy formats.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Readable, Writable)]
+pub enum ImageCompression {
+    Uncompressed,
+    Fast,
+    Basic,
+    Slow,
+    /// Opaque, low quality, half the size of BC7.
+    Bc1,
+    /// Legacy interpolated alpha.
+    Bc3,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Readable, Writable)]
 pub struct ImageSourceDesc {
     pub ty: ImageType,
     pub generate_mips: bool,
-    pub need_compression: bool,
+    /// Compression preset, project-wide default if None. Default is filled in
+    /// when source is queued for import.
+    pub compression: Option<ImageCompression>,
     pub srgb: bool,
 }
 
@@ -41,7 +59,7 @@ impl ImageSourceDesc {
         Self {
             ty: ImageType::Rgba,
             generate_mips: true,
-            need_compression: true,
+            compression: None,
             srgb: true,
         }
     }
@@ -50,7 +68,7 @@ impl ImageSourceDesc {
         Self {
             ty: ImageType::Rgba,
             generate_mips: true,
-            need_compression: true,
+            compression: None,
             srgb: false,
         }
     }
@@ -59,10 +77,16 @@ impl ImageSourceDesc {
         Self {
             ty: ImageType::Rg,
             generate_mips: true,
-            need_compression: true,
+            compression: None,
             srgb: false,
         }
     }
+
+    pub fn compression(mut self, compression: ImageCompression) -> Self {
+        self.compression = Some(compression);
+
+        self
+    }
 }
 
 #[derive(Debug)]
@@ -115,6 +139,52 @@ impl ImageSource {
     }
 }
 
+/// Where single channel of packed image comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Readable, Writable, Hash)]
+pub enum ChannelSource {
+    /// Take channel with given index from image.
+    Image(ImageDataSource, u8),
+    Constant(u8),
+}
+
+impl ChannelSource {
+    pub fn image<P: AsRef<Path>>(path: P, channel: u8) -> Self {
+        Self::Image(
+            ImageDataSource::File(path.as_ref().to_str().unwrap().to_owned()),
+            channel,
+        )
+    }
+
+    pub fn constant(value: f32) -> Self {
+        Self::Constant((value.clamp(0.0, 1.0) * 255.0) as u8)
+    }
+}
+
+/// Image composed from channels of several images.
+///
+/// Used to pack masks like occlusion, roughness and metallic into one texture.
+/// Inputs of different dimensions are resized to largest one.
+#[derive(Debug, Clone, PartialEq, Eq, Readable, Writable, Hash)]
+pub struct PackedImageSource {
+    /// Sources for red, green, blue and alpha channels.
+    pub channels: [ChannelSource; 4],
+    pub desc: ImageSourceDesc,
+}
+
+impl ContentSource for PackedImageSource {
+    fn get_ref(&self) -> AssetRef {
+        let mut hasher = siphasher::sip128::SipHasher::default();
+        self.hash(&mut hasher);
+        hasher.finish128().as_u128().into()
+    }
+}
+
+impl PackedImageSource {
+    pub fn new(channels: [ChannelSource; 4], desc: ImageSourceDesc) -> Self {
+        Self { channels, desc }
+    }
+}
+
 fn color_to_pixles(color: [f32; 4]) -> [u8; 4] {
     [
         (color[0].clamp(0.0, 1.0) * 255.0) as u8,
@@ -128,6 +198,10 @@ fn color_to_pixles(color: [f32; 4]) -> [u8; 4] {
 pub struct ImageAsset {
     pub format: vk::Format,
     pub dimensions: [u32; 2],
+    /// Number of array layers, every cubemap face is a separate layer.
+    pub layers: u32,
+    pub cubemap: bool,
+    /// Every mip level contains data for all layers.
     pub mips: Vec<Vec<u8>>,
 }
 
@@ -136,6 +210,8 @@ impl<'a, C: Context> Readable<'a, C> for ImageAsset {
         Ok(Self {
             format: vk::Format::from_raw(reader.read_i32()?),
             dimensions: reader.read_value()?,
+            layers: reader.read_value()?,
+            cubemap: reader.read_value()?,
             mips: reader.read_value()?,
         })
     }
@@ -145,6 +221,8 @@ impl<C: Context> Writable<C> for ImageAsset {
     fn write_to<T: ?Sized + speedy::Writer<C>>(&self, writer: &mut T) -> Result<(), C::Error> {
         writer.write_i32(self.format.as_raw())?;
         writer.write_value(&self.dimensions)?;
+        writer.write_value(&self.layers)?;
+        writer.write_value(&self.cubemap)?;
         writer.write_value(&self.mips)?;
 
         Ok(())
@@ -18,6 +18,7 @@ use std::{
     fmt::{Debug, Display},
     io::{self},
     path::{Path, PathBuf},
+    str::FromStr,
 };
 
 use bytes::Bytes;
@@ -25,12 +26,18 @@ use downcast_rs::{impl_downcast, DowncastSync};
 use speedy::{Readable, Writable};
 use uuid::Uuid;
 
+mod animation;
+mod atlas;
 mod image;
 mod model;
+mod scene;
 mod shader;
 
+pub use animation::*;
+pub use atlas::*;
 pub use image::*;
 pub use model::*;
+pub use scene::*;
 pub use shader::*;
 
 pub const ROOT_DATA_PATH: &str = "assets";
@@ -52,6 +59,14 @@ impl From<u128> for AssetRef {
     }
 }
 
+impl FromStr for AssetRef {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(Uuid::parse_str(s)?))
+    }
+}
+
 pub trait ContentSource: Debug + Send + Sync {
     fn get_ref(&self) -> AssetRef;
 }
@@ -16,26 +16,218 @@
 use std::{
     collections::HashMap,
     hash::{self, Hash},
+    ops::BitOr,
     path::Path,
 };
 
+use ash::vk;
 use bytes::Bytes;
+use dess_backend::SamplerDesc;
 use siphasher::sip128::Hasher128;
 use speedy::{Readable, Writable};
 
-use crate::{Asset, AssetLoad, AssetRef, ContentSource};
+use crate::{AnimationClipAsset, Asset, AssetLoad, AssetRef, ContentSource};
 
-#[derive(Debug, Clone, Hash)]
+/// Level of detail generated by mesh simplification.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeshLodDesc {
+    /// Target index count relative to base level.
+    pub ratio: f32,
+    /// Maximum deviation relative to mesh extent.
+    pub error: f32,
+}
+
+impl MeshLodDesc {
+    pub fn new(ratio: f32, error: f32) -> Self {
+        Self { ratio, error }
+    }
+}
+
+/// How normals are generated for meshes that don't have them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NormalGeneration {
+    /// Each triangle gets its own face normal.
+    Flat,
+    /// Face normals are averaged across triangles sharing a position, unless
+    /// angle between them exceeds crease angle (in degrees).
+    Smooth { crease_angle: f32 },
+}
+
+impl Default for NormalGeneration {
+    fn default() -> Self {
+        Self::Smooth { crease_angle: 60.0 }
+    }
+}
+
+/// Up axis of source file, glTF specifies Y but some exporters ignore it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum UpAxis {
+    #[default]
+    Y,
+    Z,
+}
+
+/// Fixes applied to broken source geometry, problems are reported anyway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MeshRepair {
+    /// Remove zero area triangles. Out of range indices always fail import.
+    pub drop_degenerate: bool,
+    /// Normalize normals and tangents.
+    pub renormalize: bool,
+    /// Replace NaN and infinite values with zeroes.
+    pub clamp: bool,
+}
+
+impl Default for MeshRepair {
+    fn default() -> Self {
+        Self {
+            drop_degenerate: true,
+            renormalize: true,
+            clamp: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct GltfSource {
     pub path: String,
+    /// Maximum error of animation keys removed during keyframe reduction.
+    pub animation_tolerance: f32,
+    pub lods: Vec<MeshLodDesc>,
+    /// Split meshes into meshlets for GPU driven culling.
+    pub meshlets: bool,
+    /// Store vertices in compact quantized layout.
+    pub quantize: bool,
+    pub normals: NormalGeneration,
+    pub repair: MeshRepair,
+    /// Uniform scale from source units to meters.
+    pub scale: f32,
+    /// Source is converted to Y up.
+    pub up_axis: UpAxis,
+    /// Mirror Z axis to convert right handed source to left handed.
+    pub flip_handedness: bool,
+    /// Move transform of root node into its mesh and children, so models
+    /// start at origin. Animations of children are adjusted, animated root
+    /// fails import.
+    pub bake_root_transform: bool,
+    /// Merge static meshes of each model into combined meshes attached to
+    /// root, one submesh per material.
+    pub batch: bool,
 }
 
 impl GltfSource {
     pub fn new<P: AsRef<Path>>(path: P) -> Self {
         Self {
             path: path.as_ref().to_str().unwrap().to_owned(),
+            animation_tolerance: 0.0001,
+            lods: vec![
+                MeshLodDesc::new(0.5, 0.01),
+                MeshLodDesc::new(0.25, 0.02),
+                MeshLodDesc::new(0.125, 0.05),
+            ],
+            meshlets: false,
+            quantize: false,
+            normals: NormalGeneration::default(),
+            repair: MeshRepair::default(),
+            scale: 1.0,
+            up_axis: UpAxis::default(),
+            flip_handedness: false,
+            bake_root_transform: false,
+            batch: false,
         }
     }
+
+    pub fn lods(mut self, lods: &[MeshLodDesc]) -> Self {
+        self.lods = lods.to_vec();
+
+        self
+    }
+
+    pub fn meshlets(mut self, value: bool) -> Self {
+        self.meshlets = value;
+
+        self
+    }
+
+    pub fn quantize(mut self, value: bool) -> Self {
+        self.quantize = value;
+
+        self
+    }
+
+    pub fn animation_tolerance(mut self, value: f32) -> Self {
+        self.animation_tolerance = value;
+
+        self
+    }
+
+    pub fn normals(mut self, value: NormalGeneration) -> Self {
+        self.normals = value;
+
+        self
+    }
+
+    pub fn repair(mut self, value: MeshRepair) -> Self {
+        self.repair = value;
+
+        self
+    }
+
+    pub fn scale(mut self, value: f32) -> Self {
+        self.scale = value;
+
+        self
+    }
+
+    pub fn up_axis(mut self, value: UpAxis) -> Self {
+        self.up_axis = value;
+
+        self
+    }
+
+    pub fn flip_handedness(mut self, value: bool) -> Self {
+        self.flip_handedness = value;
+
+        self
+    }
+
+    pub fn bake_root_transform(mut self, value: bool) -> Self {
+        self.bake_root_transform = value;
+
+        self
+    }
+
+    pub fn batch(mut self, value: bool) -> Self {
+        self.batch = value;
+
+        self
+    }
+}
+
+impl Hash for GltfSource {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.path.hash(state);
+        self.animation_tolerance.to_bits().hash(state);
+        for lod in &self.lods {
+            lod.ratio.to_bits().hash(state);
+            lod.error.to_bits().hash(state);
+        }
+        self.meshlets.hash(state);
+        self.quantize.hash(state);
+        match self.normals {
+            NormalGeneration::Flat => 0u8.hash(state),
+            NormalGeneration::Smooth { crease_angle } => {
+                1u8.hash(state);
+                crease_angle.to_bits().hash(state);
+            }
+        }
+        self.repair.hash(state);
+        self.scale.to_bits().hash(state);
+        self.up_axis.hash(state);
+        self.flip_handedness.hash(state);
+        self.bake_root_transform.hash(state);
+        self.batch.hash(state);
+    }
 }
 
 impl ContentSource for GltfSource {
@@ -78,6 +270,63 @@ impl MeshVertexAttributes {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Readable, Writable)]
+#[repr(C)]
+pub struct QuantizedMeshVertex {
+    /// Normalized position inside of mesh bounds, last component is padding.
+    pub position: [u16; 4],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Readable, Writable)]
+#[repr(C)]
+pub struct QuantizedMeshAttributes {
+    /// Octahedral encoded unit vectors.
+    pub normal: [i16; 2],
+    pub tangent: [i16; 2],
+    /// Half float texture coordinates.
+    pub uv1: [u16; 2],
+    pub uv2: [u16; 2],
+}
+
+/// Restores quantized position: offset + position * scale.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Readable, Writable)]
+pub struct MeshQuantization {
+    pub position_offset: [f32; 3],
+    pub position_scale: [f32; 3],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Readable, Writable)]
+#[repr(C)]
+pub struct SkinnedMeshVertex {
+    pub joints: [u16; 4],
+    pub weights: [f32; 4],
+}
+
+impl SkinnedMeshVertex {
+    pub fn new(joints: [u16; 4], weights: [f32; 4]) -> Self {
+        Self { joints, weights }
+    }
+}
+
+/// Difference from base mesh for single vertex of morph target.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Readable, Writable)]
+#[repr(C)]
+pub struct MorphTargetDelta {
+    /// Vertex index relative to mesh vertex offset.
+    pub vertex: u32,
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub tangent: [f32; 3],
+}
+
+#[derive(Debug, Clone, PartialEq, Readable, Writable)]
+pub struct MorphTarget {
+    pub name: String,
+    /// Range in `morph_deltas`, only vertices affected by target are stored.
+    pub first_delta: u32,
+    pub delta_count: u32,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Readable, Writable)]
 pub struct Bone {
     pub parent: Option<u32>,
@@ -86,19 +335,173 @@ pub struct Bone {
     pub local_scale: [f32; 3],
 }
 
+/// Axis aligned box and sphere enclosing geometry.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Readable, Writable)]
+pub struct BoundingVolume {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+    pub center: [f32; 3],
+    pub radius: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash, Readable, Writable)]
+pub struct SubMeshFlags(u32);
+
+impl SubMeshFlags {
+    /// Submesh has joints and weights in skinned vertex stream.
+    pub const SKINNED: Self = Self(1);
+    /// Submesh is affected by morph targets.
+    pub const MORPHED: Self = Self(1 << 1);
+
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for SubMeshFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// Small cluster of triangles with culling data.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Readable, Writable)]
+#[repr(C)]
+pub struct Meshlet {
+    /// Offset in `meshlet_vertices`, those are relative to mesh vertex offset.
+    pub vertex_offset: u32,
+    /// Offset in `meshlet_triangles`, three local vertex indices per triangle.
+    pub triangle_offset: u32,
+    pub vertex_count: u32,
+    pub triangle_count: u32,
+    pub center: [f32; 3],
+    pub radius: f32,
+    pub cone_apex: [f32; 3],
+    pub cone_cutoff: f32,
+    pub cone_axis: [f32; 3],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Readable, Writable)]
+pub struct SubMeshLod {
+    pub first_index: u32,
+    pub index_count: u32,
+    /// Geometric deviation from base level in model units.
+    pub error: f32,
+}
+
 #[derive(Debug, Clone, PartialEq, Readable, Writable)]
 pub struct SubMesh {
     pub first_index: u32,
     pub index_count: u32,
-    pub bounds: ([f32; 3], [f32; 3]),
+    pub bounds: BoundingVolume,
     pub material: u32,
+    pub flags: SubMeshFlags,
+    /// Simplified levels from finest to coarsest, base level is not included.
+    pub lods: Vec<SubMeshLod>,
+    /// Range in `meshlets` for base level, empty if meshlets weren't built.
+    pub first_meshlet: u32,
+    pub meshlet_count: u32,
+}
+
+impl SubMesh {
+    /// Returns first index and index count of level, level 0 is base one.
+    pub fn lod(&self, level: usize) -> (u32, u32) {
+        if level == 0 || self.lods.is_empty() {
+            (self.first_index, self.index_count)
+        } else {
+            let lod = &self.lods[(level - 1).min(self.lods.len() - 1)];
+            (lod.first_index, lod.index_count)
+        }
+    }
+
+    /// Picks coarsest level with error visible from distance below threshold.
+    /// Threshold is error to distance ratio, so it accounts for projection.
+    pub fn select_lod(&self, distance: f32, threshold: f32) -> usize {
+        self.lods
+            .iter()
+            .rposition(|lod| lod.error <= distance * threshold)
+            .map(|x| x + 1)
+            .unwrap_or(0)
+    }
+}
+
+/// Set of vertex streams present in mesh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash, Readable, Writable)]
+pub struct MeshStreams(u32);
+
+impl MeshStreams {
+    /// Positions, full precision or quantized.
+    pub const POSITIONS: Self = Self(1);
+    /// Normals, tangents and texture coordinates.
+    pub const ATTRIBUTES: Self = Self(1 << 1);
+    pub const SKINNED: Self = Self(1 << 2);
+    pub const COLORS: Self = Self(1 << 3);
+    pub const CUSTOM: Self = Self(1 << 4);
+
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for MeshStreams {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// Named attribute that engine doesn't know about, like `_WIND` in glTF.
+#[derive(Debug, Clone, PartialEq, Readable, Writable)]
+pub struct MeshCustomStream {
+    pub name: String,
+    /// From one to four float components per vertex.
+    pub components: u32,
+    /// Offset in `custom_attributes`, stream goes in parallel with vertices.
+    pub offset: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Readable, Writable)]
+pub enum MeshIndexType {
+    #[default]
+    U16,
+    /// Used for meshes with more than 65536 vertices.
+    U32,
 }
 
 #[derive(Debug, Default, Readable, Writable)]
 pub struct MeshData {
+    /// Offset in `vertices` and `attributes`, or in quantized streams if
+    /// mesh is quantized.
     pub vertex_offset: u32,
+    /// Offset in `indices` or `wide_indices` depending on index type.
     pub index_offset: u32,
+    pub index_type: MeshIndexType,
+    pub quantization: Option<MeshQuantization>,
+    /// Offset in `skinned_vertices` if any of submeshes is skinned. Skinned
+    /// stream goes in parallel with vertices starting at `vertex_offset`.
+    pub skinned_offset: Option<u32>,
+    /// Offset in `colors`, goes in parallel with vertices.
+    pub color_offset: Option<u32>,
+    pub custom_streams: Vec<MeshCustomStream>,
+    pub streams: MeshStreams,
+    pub morph_targets: Vec<MorphTarget>,
+    pub morph_weights: Vec<f32>,
     pub submeshes: Vec<SubMesh>,
+    /// Encloses all submeshes in rest pose.
+    pub bounds: BoundingVolume,
+}
+
+impl MeshData {
+    pub fn lod_count(&self) -> usize {
+        self.submeshes
+            .iter()
+            .map(|x| x.lods.len() + 1)
+            .max()
+            .unwrap_or(1)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Readable, Writable)]
@@ -119,15 +522,263 @@ impl Hash for MeshBlendMode {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Readable, Writable)]
+pub enum TextureFilter {
+    Nearest,
+    #[default]
+    Linear,
+}
+
+impl TextureFilter {
+    fn texel_filter(&self) -> vk::Filter {
+        match self {
+            Self::Nearest => vk::Filter::NEAREST,
+            Self::Linear => vk::Filter::LINEAR,
+        }
+    }
+
+    fn mipmap_mode(&self) -> vk::SamplerMipmapMode {
+        match self {
+            Self::Nearest => vk::SamplerMipmapMode::NEAREST,
+            Self::Linear => vk::SamplerMipmapMode::LINEAR,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Readable, Writable)]
+pub enum TextureAddressMode {
+    #[default]
+    Repeat,
+    ClampToEdge,
+    MirroredRepeat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Readable, Writable)]
+pub struct TextureSampler {
+    pub filter: TextureFilter,
+    pub mipmap_filter: TextureFilter,
+    pub address_mode: TextureAddressMode,
+}
+
+impl TextureSampler {
+    pub fn sampler_desc(&self) -> SamplerDesc {
+        SamplerDesc {
+            texel_filter: self.filter.texel_filter(),
+            mipmap_mode: self.mipmap_filter.mipmap_mode(),
+            address_mode: match self.address_mode {
+                TextureAddressMode::Repeat => vk::SamplerAddressMode::REPEAT,
+                TextureAddressMode::ClampToEdge => vk::SamplerAddressMode::CLAMP_TO_EDGE,
+                TextureAddressMode::MirroredRepeat => vk::SamplerAddressMode::MIRRORED_REPEAT,
+            },
+            anisotropy_level: if self.filter == TextureFilter::Linear {
+                16
+            } else {
+                0
+            },
+        }
+    }
+}
+
+/// Transformation of texture coordinates: uv = offset + rotate(uv * scale).
+#[derive(Debug, Clone, Copy, PartialEq, Readable, Writable)]
+pub struct TextureTransform {
+    pub offset: [f32; 2],
+    pub scale: [f32; 2],
+    /// Counter-clockwise rotation in radians.
+    pub rotation: f32,
+}
+
+impl Default for TextureTransform {
+    fn default() -> Self {
+        Self {
+            offset: [0.0, 0.0],
+            scale: [1.0, 1.0],
+            rotation: 0.0,
+        }
+    }
+}
+
+impl Hash for TextureTransform {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.offset
+            .iter()
+            .chain(self.scale.iter())
+            .chain([self.rotation].iter())
+            .for_each(|x| ((x * 1000.0) as i64).hash(state));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Hash, Readable, Writable)]
+pub struct MaterialTexture {
+    pub texture: AssetRef,
+    pub sampler: TextureSampler,
+    /// Index of texture coordinates set.
+    pub uv_set: u32,
+    pub transform: TextureTransform,
+}
+
+impl Eq for MaterialTexture {}
+
+impl MaterialTexture {
+    pub fn new(texture: AssetRef) -> Self {
+        Self {
+            texture,
+            sampler: TextureSampler::default(),
+            uv_set: 0,
+            transform: TextureTransform::default(),
+        }
+    }
+}
+
+fn hash_float<H: hash::Hasher>(value: f32, state: &mut H) {
+    ((value * 1000.0) as i64).hash(state);
+}
+
+/// KHR_materials_clearcoat
+#[derive(Debug, Clone, PartialEq, Readable, Writable)]
+pub struct MaterialClearcoat {
+    pub factor: f32,
+    /// Intensity in R channel.
+    pub texture: Option<MaterialTexture>,
+    pub roughness_factor: f32,
+    /// Roughness in G channel.
+    pub roughness_texture: Option<MaterialTexture>,
+    pub normal_texture: Option<MaterialTexture>,
+}
+
+impl Hash for MaterialClearcoat {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        hash_float(self.factor, state);
+        self.texture.hash(state);
+        hash_float(self.roughness_factor, state);
+        self.roughness_texture.hash(state);
+        self.normal_texture.hash(state);
+    }
+}
+
+/// KHR_materials_sheen
+#[derive(Debug, Clone, PartialEq, Readable, Writable)]
+pub struct MaterialSheen {
+    pub color_factor: [f32; 3],
+    pub color_texture: Option<MaterialTexture>,
+    pub roughness_factor: f32,
+    /// Roughness in A channel.
+    pub roughness_texture: Option<MaterialTexture>,
+}
+
+impl Hash for MaterialSheen {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.color_factor.iter().for_each(|x| hash_float(*x, state));
+        self.color_texture.hash(state);
+        hash_float(self.roughness_factor, state);
+        self.roughness_texture.hash(state);
+    }
+}
+
+/// KHR_materials_transmission
+#[derive(Debug, Clone, PartialEq, Readable, Writable)]
+pub struct MaterialTransmission {
+    pub factor: f32,
+    /// Transmission in R channel.
+    pub texture: Option<MaterialTexture>,
+}
+
+impl Hash for MaterialTransmission {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        hash_float(self.factor, state);
+        self.texture.hash(state);
+    }
+}
+
+/// KHR_materials_specular
+#[derive(Debug, Clone, PartialEq, Readable, Writable)]
+pub struct MaterialSpecular {
+    pub factor: f32,
+    /// Strength in A channel.
+    pub texture: Option<MaterialTexture>,
+    pub color_factor: [f32; 3],
+    pub color_texture: Option<MaterialTexture>,
+}
+
+impl Hash for MaterialSpecular {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        hash_float(self.factor, state);
+        self.texture.hash(state);
+        self.color_factor.iter().for_each(|x| hash_float(*x, state));
+        self.color_texture.hash(state);
+    }
+}
+
+/// KHR_materials_volume
+#[derive(Debug, Clone, PartialEq, Readable, Writable)]
+pub struct MaterialVolume {
+    pub thickness_factor: f32,
+    /// Thickness in G channel.
+    pub thickness_texture: Option<MaterialTexture>,
+    /// Infinite if not attenuated.
+    pub attenuation_distance: f32,
+    pub attenuation_color: [f32; 3],
+}
+
+impl Hash for MaterialVolume {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        hash_float(self.thickness_factor, state);
+        self.thickness_texture.hash(state);
+        hash_float(self.attenuation_distance.min(1e9), state);
+        self.attenuation_color
+            .iter()
+            .for_each(|x| hash_float(*x, state));
+    }
+}
+
+/// Optional extended PBR data, None if material doesn't use extension.
+#[derive(Debug, Clone, Default, PartialEq, Readable, Writable)]
+pub struct MaterialExtensions {
+    pub clearcoat: Option<MaterialClearcoat>,
+    pub sheen: Option<MaterialSheen>,
+    pub transmission: Option<MaterialTransmission>,
+    pub specular: Option<MaterialSpecular>,
+    pub volume: Option<MaterialVolume>,
+    /// KHR_materials_ior
+    pub ior: Option<f32>,
+    /// KHR_materials_unlit
+    pub unlit: bool,
+}
+
+impl Hash for MaterialExtensions {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.clearcoat.hash(state);
+        self.sheen.hash(state);
+        self.transmission.hash(state);
+        self.specular.hash(state);
+        self.volume.hash(state);
+        if let Some(ior) = self.ior {
+            hash_float(ior, state);
+        }
+        self.unlit.hash(state);
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Readable, Writable)]
 pub struct MeshMaterial {
     pub blend: MeshBlendMode,
-    pub base: AssetRef,
-    pub normals: AssetRef,
-    pub metallic_roughness: AssetRef,
-    pub occlusion: AssetRef,
-    pub emissive: AssetRef,
+    pub base: MaterialTexture,
+    pub normals: MaterialTexture,
+    /// Packed texture: R - occlusion, G - roughness, B - metallic.
+    pub occlusion_roughness_metallic: MaterialTexture,
+    /// Occlusion sampled with other coordinates or transform than roughness
+    /// and metallic, red channel of packed texture is white then.
+    pub occlusion: Option<MaterialTexture>,
+    pub emissive: MaterialTexture,
     pub emission_power: f32,
+    /// Factors are multiplied with texture values.
+    pub base_color_factor: [f32; 4],
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    pub emissive_factor: [f32; 3],
+    pub normal_scale: f32,
+    pub occlusion_strength: f32,
+    pub extensions: MaterialExtensions,
 }
 
 impl Eq for MeshMaterial {}
@@ -137,29 +788,77 @@ impl Hash for MeshMaterial {
         self.blend.hash(state);
         self.base.hash(state);
         self.normals.hash(state);
-        self.metallic_roughness.hash(state);
+        self.occlusion_roughness_metallic.hash(state);
         self.occlusion.hash(state);
         self.emissive.hash(state);
         ((self.emission_power * 1000.0) as u64).hash(state);
+        self.base_color_factor
+            .iter()
+            .chain([self.metallic_factor, self.roughness_factor].iter())
+            .chain(self.emissive_factor.iter())
+            .chain([self.normal_scale, self.occlusion_strength].iter())
+            .for_each(|x| hash_float(*x, state));
+        self.extensions.hash(state);
     }
 }
 
+/// Joints of skin in order used by skinned vertices.
+#[derive(Debug, Default, Clone, PartialEq, Readable, Writable)]
+pub struct Skin {
+    /// Bone index for each joint.
+    pub joints: Vec<u32>,
+    pub inverse_bind_matrices: Vec<[[f32; 4]; 4]>,
+}
+
+/// Triangles of node merged into batched mesh, used for picking.
+#[derive(Debug, Clone, PartialEq, Eq, Readable, Writable)]
+pub struct BatchedNode {
+    /// Node path, same as in `ModelAsset::bone_names`.
+    pub path: String,
+    /// Index in `ModelCollectionAsset::meshes`.
+    pub mesh: u32,
+    pub submesh: u32,
+    pub first_index: u32,
+    pub index_count: u32,
+}
+
 #[derive(Debug, Default, Readable, Writable)]
 pub struct ModelAsset {
-    pub static_meshes: Vec<MeshData>,
+    /// Mesh name -> index in `ModelCollectionAsset::meshes`.
     pub mesh_names: HashMap<String, u32>,
     pub bones: Vec<Bone>,
     pub bone_names: HashMap<String, u32>,
+    /// Bone index -> index in `ModelCollectionAsset::meshes`, meshes are
+    /// shared between all instances and models of collection.
     pub node_to_mesh: Vec<(u32, u32)>,
+    pub skins: Vec<Skin>,
+    pub node_to_skin: Vec<(u32, u32)>,
+    pub batched_nodes: Vec<BatchedNode>,
+    /// Encloses all meshes placed by bones in rest pose, skinned meshes are
+    /// placed by their joints.
+    pub bounds: BoundingVolume,
 }
 
 #[derive(Debug, Default, Readable, Writable)]
 pub struct ModelCollectionAsset {
     pub vertices: Vec<StaticMeshVertex>,
     pub attributes: Vec<MeshVertexAttributes>,
+    pub quantized_vertices: Vec<QuantizedMeshVertex>,
+    pub quantized_attributes: Vec<QuantizedMeshAttributes>,
+    pub skinned_vertices: Vec<SkinnedMeshVertex>,
+    /// Linear RGBA vertex colors.
+    pub colors: Vec<[u8; 4]>,
+    pub custom_attributes: Vec<f32>,
+    pub morph_deltas: Vec<MorphTargetDelta>,
     pub indices: Vec<u16>,
+    pub wide_indices: Vec<u32>,
+    pub meshlets: Vec<Meshlet>,
+    pub meshlet_vertices: Vec<u32>,
+    pub meshlet_triangles: Vec<u8>,
     pub materials: Vec<MeshMaterial>,
+    pub meshes: Vec<MeshData>,
     pub models: HashMap<String, ModelAsset>,
+    pub animations: HashMap<String, AnimationClipAsset>,
 }
 
 impl Asset for ModelCollectionAsset {
@@ -0,0 +1,162 @@
+// Copyright (C) 2023 Vladimir Kuskov
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{
+    collections::HashMap,
+    hash::{self, Hash},
+    path::Path,
+};
+
+use bytes::Bytes;
+use siphasher::sip128::Hasher128;
+use speedy::{Readable, Writable};
+
+use crate::{Asset, AssetLoad, AssetRef, Bone, ContentSource, UpAxis};
+
+/// Node hierarchy, cameras and lights of glTF default scene. Coordinate
+/// conversion must match one of `GltfSource` used for models of the scene.
+#[derive(Debug, Clone)]
+pub struct GltfSceneSource {
+    pub path: String,
+    /// Uniform scale from source units to meters.
+    pub scale: f32,
+    /// Source is converted to Y up.
+    pub up_axis: UpAxis,
+    /// Mirror Z axis to convert right handed source to left handed.
+    pub flip_handedness: bool,
+}
+
+impl GltfSceneSource {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_str().unwrap().to_owned(),
+            scale: 1.0,
+            up_axis: UpAxis::default(),
+            flip_handedness: false,
+        }
+    }
+
+    pub fn scale(mut self, value: f32) -> Self {
+        self.scale = value;
+
+        self
+    }
+
+    pub fn up_axis(mut self, value: UpAxis) -> Self {
+        self.up_axis = value;
+
+        self
+    }
+
+    pub fn flip_handedness(mut self, value: bool) -> Self {
+        self.flip_handedness = value;
+
+        self
+    }
+}
+
+impl Hash for GltfSceneSource {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.path.hash(state);
+        self.scale.to_bits().hash(state);
+        self.up_axis.hash(state);
+        self.flip_handedness.hash(state);
+    }
+}
+
+impl ContentSource for GltfSceneSource {
+    fn get_ref(&self) -> AssetRef {
+        let mut hasher = siphasher::sip128::SipHasher::default();
+        // Same file is also imported as model collection
+        "scene".hash(&mut hasher);
+        self.hash(&mut hasher);
+        hasher.finish128().as_u128().into()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Readable, Writable)]
+pub enum SceneCamera {
+    Perspective {
+        /// Vertical field of view in radians.
+        yfov: f32,
+        /// Viewport aspect ratio is used when not set.
+        aspect_ratio: Option<f32>,
+        znear: f32,
+        /// Infinite projection when not set.
+        zfar: Option<f32>,
+    },
+    Orthographic {
+        xmag: f32,
+        ymag: f32,
+        znear: f32,
+        zfar: f32,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Readable, Writable)]
+pub enum SceneLightKind {
+    Directional,
+    Point,
+    /// Cone angles are in radians.
+    Spot {
+        inner_cone_angle: f32,
+        outer_cone_angle: f32,
+    },
+}
+
+/// KHR_lights_punctual light, shines along -Z of its node.
+#[derive(Debug, Clone, Copy, PartialEq, Readable, Writable)]
+pub struct SceneLight {
+    pub kind: SceneLightKind,
+    /// Linear RGB.
+    pub color: [f32; 3],
+    /// Candela for point and spot lights, lux for directional.
+    pub intensity: f32,
+    /// Infinite range when not set.
+    pub range: Option<f32>,
+}
+
+/// Value of node `extras` property.
+#[derive(Debug, Clone, PartialEq, Readable, Writable)]
+pub enum SceneExtra {
+    Bool(bool),
+    Number(f64),
+    String(String),
+    /// Arrays and objects are kept as JSON text.
+    Json(String),
+}
+
+#[derive(Debug, Default, Readable, Writable)]
+pub struct SceneAsset {
+    pub nodes: Vec<Bone>,
+    /// Node path -> node index, path is built from node names like bone names
+    /// in `ModelAsset`.
+    pub node_names: HashMap<String, u32>,
+    pub node_to_camera: Vec<(u32, SceneCamera)>,
+    pub node_to_light: Vec<(u32, SceneLight)>,
+    pub node_to_extras: Vec<(u32, HashMap<String, SceneExtra>)>,
+}
+
+impl Asset for SceneAsset {
+    fn to_bytes(&self) -> std::io::Result<Bytes> {
+        Ok(self.write_to_vec()?.into())
+    }
+}
+
+impl AssetLoad for SceneAsset {
+    fn from_bytes(data: &[u8]) -> std::io::Result<Self> {
+        Ok(Self::read_from_buffer(data)?)
+    }
+}
@@ -31,6 +31,7 @@ pub struct ImageDesc {
     pub format: vk::Format,
     pub mip_levels: u32,
     pub array_elements: u32,
+    pub flags: vk::ImageCreateFlags,
     pub tiling: vk::ImageTiling,
     pub name: Option<SmolStr>,
 }
@@ -95,6 +96,15 @@ impl ImageViewDesc {
     }
 
     fn build(&self, image: &Image) -> vk::ImageViewCreateInfo {
+        let ty = self
+            .ty
+            .unwrap_or_else(|| Self::convert_image_type_to_view_type(image));
+        let layer_count = match ty {
+            vk::ImageViewType::TYPE_1D
+            | vk::ImageViewType::TYPE_2D
+            | vk::ImageViewType::TYPE_3D => 1,
+            _ => image.desc.array_elements,
+        };
         vk::ImageViewCreateInfo::builder()
             .format(self.format.unwrap_or(image.desc.format))
             .components(vk::ComponentMapping {
@@ -103,23 +113,28 @@ impl ImageViewDesc {
                 b: vk::ComponentSwizzle::B,
                 a: vk::ComponentSwizzle::A,
             })
-            .view_type(
-                self.ty
-                    .unwrap_or_else(|| Self::convert_image_type_to_view_type(image)),
-            )
+            .view_type(ty)
             .subresource_range(vk::ImageSubresourceRange {
                 aspect_mask: self.aspect,
                 base_mip_level: self.base_mip_level,
                 level_count: self.level_count.unwrap_or(image.desc.mip_levels),
                 base_array_layer: 0,
-                layer_count: 1,
+                layer_count,
             })
             .image(image.raw)
             .build()
     }
 
     fn convert_image_type_to_view_type(image: &Image) -> vk::ImageViewType {
+        let cube = image
+            .desc
+            .flags
+            .contains(vk::ImageCreateFlags::CUBE_COMPATIBLE);
         match image.desc.ty {
+            vk::ImageType::TYPE_2D if cube && image.desc.array_elements == 6 => {
+                vk::ImageViewType::CUBE
+            }
+            vk::ImageType::TYPE_2D if cube => vk::ImageViewType::CUBE_ARRAY,
             vk::ImageType::TYPE_1D if image.desc.array_elements == 1 => vk::ImageViewType::TYPE_1D,
             vk::ImageType::TYPE_1D => vk::ImageViewType::TYPE_1D_ARRAY,
             vk::ImageType::TYPE_2D if image.desc.array_elements == 1 => vk::ImageViewType::TYPE_2D,
@@ -180,6 +195,7 @@ impl Image {
                 tiling: desc.tiling,
                 mip_levels: desc.mip_levels as u32,
                 array_elements: desc.array_elements as u32,
+                flags: desc.flags,
                 name: desc.name.map(|x| x.into()),
             },
             memory: Some(memory),
@@ -143,6 +143,39 @@ impl VertexAttribute for glam::I16Vec4 {
     }
 }
 
+/// Four 16 bit values normalized to [0, 1] range.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct UNorm16Vec4(pub [u16; 4]);
+
+impl VertexAttribute for UNorm16Vec4 {
+    fn vk_format() -> vk::Format {
+        vk::Format::R16G16B16A16_UNORM
+    }
+}
+
+/// Two 16 bit values normalized to [-1, 1] range.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct SNorm16Vec2(pub [i16; 2]);
+
+impl VertexAttribute for SNorm16Vec2 {
+    fn vk_format() -> vk::Format {
+        vk::Format::R16G16_SNORM
+    }
+}
+
+/// Two half floats.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct HalfVec2(pub [u16; 2]);
+
+impl VertexAttribute for HalfVec2 {
+    fn vk_format() -> vk::Format {
+        vk::Format::R16G16_SFLOAT
+    }
+}
+
 impl InputVertexStreamDesc {
     pub fn attrubute<T: VertexAttribute>(mut self, offset: usize) -> Self {
         self.attributes
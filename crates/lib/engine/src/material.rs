@@ -121,24 +121,18 @@ pub const MESH_PBR_MATERIAL_LAYOUT: BindGroupLayoutDesc = BindGroupLayoutDesc {
         },
         DescriptorBindingDesc {
             slot: 2,
-            name: "metallic_roughness",
+            name: "occlusion_roughness_metallic",
             ty: BindType::SampledImage,
             count: 1,
         },
         DescriptorBindingDesc {
             slot: 3,
-            name: "occlusion",
-            ty: BindType::SampledImage,
-            count: 1,
-        },
-        DescriptorBindingDesc {
-            slot: 4,
             name: "emissive",
             ty: BindType::SampledImage,
             count: 1,
         },
         DescriptorBindingDesc {
-            slot: 5,
+            slot: 4,
             name: "material",
             ty: BindType::UniformBuffer,
             count: 1,
@@ -167,11 +161,13 @@ pub const MESH_SHADOW_LAYOUT: BindGroupLayoutDesc = BindGroupLayoutDesc {
 impl Material {
     pub fn new<T: ResourceLoader>(loader: &T, mesh_material: &MeshMaterial) -> Result<Self, Error> {
         let mut images = HashMap::new();
-        images.insert("base", mesh_material.base);
-        images.insert("normals", mesh_material.normals);
-        images.insert("metallic_roughness", mesh_material.metallic_roughness);
-        images.insert("occlusion", mesh_material.occlusion);
-        images.insert("emissive", mesh_material.emissive);
+        images.insert("base", mesh_material.base.texture);
+        images.insert("normals", mesh_material.normals.texture);
+        images.insert(
+            "occlusion_roughness_metallic",
+            mesh_material.occlusion_roughness_metallic.texture,
+        );
+        images.insert("emissive", mesh_material.emissive.texture);
         let images = images
             .into_iter()
             .map(|(name, image)| (name.into(), loader.request_image(image)))
@@ -15,11 +15,12 @@
 
 use std::{collections::HashMap, sync::Arc};
 
-use dess_assets::{MeshData, ModelAsset, ModelCollectionAsset};
+use ash::vk;
+use dess_assets::{MeshData, MeshIndexType, ModelAsset, ModelCollectionAsset};
 use dess_backend::{
-    BindGroupHandle, BindGroupLayoutDesc, BindType, BufferSlice, DescriptorBindingDesc, Device,
-    ShaderStage,
+    BindGroupHandle, BindGroupLayoutDesc, BindType, DescriptorBindingDesc, Device, ShaderStage,
 };
+use dess_gfx::{BufferSlice, DrawStream};
 
 use smol_str::SmolStr;
 
@@ -50,7 +51,9 @@ pub struct SubMesh {
 pub struct StaticMesh {
     pub vertices: BufferSlice,
     pub attributes: BufferSlice,
+    /// Either 16 or 32 bit index buffer of collection, see `index_type`.
     pub indices: BufferSlice,
+    pub index_type: vk::IndexType,
     pub submeshes: Vec<SubMesh>,
     pub materials: Vec<ResourceHandle<Material>>,
     pub resolved_materials: Vec<Arc<Material>>,
@@ -73,8 +76,13 @@ impl StaticMesh {
         vertices: BufferSlice,
         attributes: BufferSlice,
         indices: BufferSlice,
+        wide_indices: BufferSlice,
         materials: &[ResourceHandle<Material>],
     ) -> Self {
+        let (indices, index_type) = match asset.index_type {
+            MeshIndexType::U16 => (indices, vk::IndexType::UINT16),
+            MeshIndexType::U32 => (wide_indices, vk::IndexType::UINT32),
+        };
         let submeshes = asset
             .submeshes
             .iter()
@@ -84,8 +92,8 @@ impl StaticMesh {
                 vertex_offset: asset.vertex_offset,
                 index_count: submesh.index_count,
                 bounds: (
-                    glam::Vec3::from_array(submesh.bounds.0),
-                    glam::Vec3::from_array(submesh.bounds.1),
+                    glam::Vec3::from_array(submesh.bounds.min),
+                    glam::Vec3::from_array(submesh.bounds.max),
                 ),
                 object_bind_group: device
                     .create_bind_group(&PACKED_MESH_OBJECT_LAYOUT)
@@ -113,12 +121,21 @@ impl StaticMesh {
             vertices,
             attributes,
             indices,
+            index_type,
             submeshes,
             resolved_materials: Vec::with_capacity(materials.len()),
             materials,
         }
     }
 
+    /// Binds mesh streams and index buffer along with its index type.
+    pub fn bind(&self, stream: &mut DrawStream) {
+        stream.bind_vertex_buffer(0, Some(self.vertices));
+        stream.bind_vertex_buffer(1, Some(self.attributes));
+        stream.bind_index_buffer(Some(self.indices));
+        stream.set_index_type(self.index_type);
+    }
+
     fn dispose(&self, ctx: &ResourceContext<'_>) {
         self.submeshes.iter().for_each(|x| {
             ctx.device.destroy_bind_group(x.object_bind_group);
@@ -150,37 +167,14 @@ pub struct Model {
     pub bone_parents: Vec<u32>,
     // Bone names
     pub bone_names: HashMap<String, u32>,
-    // Actual meshes
-    pub static_meshes: Vec<StaticMesh>,
-    // Mesh names, name->index
+    // Mesh names, name->index in collection meshes
     pub mesh_names: HashMap<String, u32>,
-    // Which mesh located where, bone->mesh
+    // Which mesh located where, bone->index in collection meshes
     pub instances: Vec<(u32, u32)>,
 }
 
-impl ResourceDependencies for Model {
-    fn is_finished(&self, ctx: &ResourceContext) -> bool {
-        self.static_meshes.iter().all(|x| x.is_finished(ctx))
-    }
-
-    fn resolve(&mut self, ctx: &ResourceContext) -> Result<(), crate::Error> {
-        for mesh in self.static_meshes.iter_mut() {
-            mesh.resolve(ctx)?;
-        }
-
-        Ok(())
-    }
-}
-
 impl Model {
-    pub fn new(
-        devce: &Device,
-        asset: ModelAsset,
-        vertices: BufferSlice,
-        attributes: BufferSlice,
-        indices: BufferSlice,
-        materials: &[ResourceHandle<Material>],
-    ) -> Self {
+    pub fn new(asset: ModelAsset) -> Self {
         let bones = asset
             .bones
             .iter()
@@ -197,44 +191,37 @@ impl Model {
             .iter()
             .map(|bone| bone.parent.unwrap_or(u32::MAX))
             .collect::<Vec<_>>();
-        let static_meshes = asset
-            .static_meshes
-            .into_iter()
-            .map(|mesh| StaticMesh::new(devce, mesh, vertices, attributes, indices, materials))
-            .collect::<Vec<_>>();
         Self {
             bones,
             bone_parents: parents,
             bone_names: asset.bone_names,
-            static_meshes,
             mesh_names: asset.mesh_names,
             instances: asset.node_to_mesh,
         }
     }
-
-    fn dispose(&self, ctx: &ResourceContext) {
-        self.static_meshes.iter().for_each(|x| x.dispose(ctx));
-    }
 }
 
 /// Representation of single gltf file
 ///
-/// Contains multiple models (scenes).
+/// Contains multiple models (scenes), meshes are shared between all of them.
 #[derive(Debug, Default)]
 pub struct ModelCollection {
     pub vertices: BufferSlice,
     pub indices: BufferSlice,
+    /// Empty slice if collection doesn't have meshes with 32 bit indices.
+    pub wide_indices: BufferSlice,
+    pub meshes: Vec<StaticMesh>,
     pub models: HashMap<SmolStr, Model>,
 }
 
 impl ResourceDependencies for ModelCollection {
     fn is_finished(&self, ctx: &ResourceContext) -> bool {
-        self.models.iter().all(|(_, x)| x.is_finished(ctx))
+        self.meshes.iter().all(|x| x.is_finished(ctx))
     }
 
     fn resolve(&mut self, ctx: &ResourceContext) -> Result<(), crate::Error> {
-        for (_, model) in self.models.iter_mut() {
-            model.resolve(ctx)?;
+        for mesh in self.meshes.iter_mut() {
+            mesh.resolve(ctx)?;
         }
 
         Ok(())
@@ -250,32 +237,42 @@ impl ModelCollection {
         let vertices = buffers.allocate(&asset.vertices)?;
         let attributes = buffers.allocate(&asset.attributes)?;
         let indices = buffers.allocate(&asset.indices)?;
+        let wide_indices = if asset.wide_indices.is_empty() {
+            BufferSlice::default()
+        } else {
+            buffers.allocate(&asset.wide_indices)?
+        };
         let materials = asset
             .materials
             .into_iter()
             .map(|material| loader.request_material(material))
             .collect::<Vec<_>>();
-        let models = asset
-            .models
+        let meshes = asset
+            .meshes
             .into_iter()
-            .map(|(name, model)| {
-                (
-                    name.into(),
-                    Model::new(
-                        loader.render_device(),
-                        model,
-                        vertices,
-                        attributes,
-                        indices,
-                        &materials,
-                    ),
+            .map(|mesh| {
+                StaticMesh::new(
+                    loader.render_device(),
+                    mesh,
+                    vertices,
+                    attributes,
+                    indices,
+                    wide_indices,
+                    &materials,
                 )
             })
+            .collect::<Vec<_>>();
+        let models = asset
+            .models
+            .into_iter()
+            .map(|(name, model)| (name.into(), Model::new(model)))
             .collect::<HashMap<_, _>>();
 
         Ok(Self {
             vertices,
             indices,
+            wide_indices,
+            meshes,
             models,
         })
     }
@@ -283,8 +280,11 @@ impl ModelCollection {
 
 impl Resource for ModelCollection {
     fn dispose(&self, ctx: &ResourceContext) {
-        self.models.iter().for_each(|(_, x)| x.dispose(ctx));
+        self.meshes.iter().for_each(|x| x.dispose(ctx));
         ctx.buffers.deallocate(self.vertices);
         ctx.buffers.deallocate(self.indices);
+        if self.wide_indices != BufferSlice::default() {
+            ctx.buffers.deallocate(self.wide_indices);
+        }
     }
 }
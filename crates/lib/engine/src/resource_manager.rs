@@ -352,8 +352,14 @@ impl ResourceManager {
                 row_pitch: 0,
             })
             .collect::<Vec<_>>();
-        let image = device.create_image(
+        let desc = if asset.cubemap {
+            ImageCreateDesc::cubemap(asset.format, asset.dimensions)
+        } else {
             ImageCreateDesc::texture(asset.format, asset.dimensions)
+        };
+        // Data of every mip level covers all layers, so layers are uploaded at once
+        let image = device.create_image(
+            desc.array_elements(asset.layers.max(1) as _)
                 .mip_levels(asset.mips.len())
                 .initial_data(&data),
         )?;
@@ -26,6 +26,7 @@ struct Draw {
     pipeline: RasterPipelineHandle,
     vertex_buffers: [BufferSlice; MAX_VERTEX_STREAMS],
     index_buffer: BufferSlice,
+    index_type: vk::IndexType,
     offsets: [u32; MAX_OFFSETS],
     first_index: u32,
     index_count: u32,
@@ -40,6 +41,7 @@ impl Default for Draw {
             pipeline: RasterPipelineHandle::default(),
             vertex_buffers: [BufferSlice::default(); MAX_VERTEX_STREAMS],
             index_buffer: BufferSlice::default(),
+            index_type: vk::IndexType::UINT16,
             offsets: [u32::MAX; MAX_OFFSETS],
             first_index: u32::MAX,
             index_count: u32::MAX,
@@ -59,6 +61,7 @@ const FIRST_INDEX: u16 = INDEX_COUNT << 1;
 const INSTANCE_COUNT: u16 = FIRST_INDEX << 1;
 const FIRST_INSTANCE: u16 = INSTANCE_COUNT << 1;
 const VERTEX_OFFSET: u16 = FIRST_INSTANCE << 1;
+const INDEX_TYPE: u16 = VERTEX_OFFSET << 1;
 
 #[derive(Debug, Clone, Copy)]
 pub struct RenderArea {
@@ -173,6 +176,14 @@ impl DrawStream {
         }
     }
 
+    /// Set size of indices in bound index buffer, 16 bit by default.
+    pub fn set_index_type(&mut self, index_type: vk::IndexType) {
+        if self.current.index_type != index_type {
+            self.mask |= INDEX_TYPE;
+            self.current.index_type = index_type;
+        }
+    }
+
     pub fn set_offset(&mut self, slot: usize, offset: Option<u32>) {
         assert!(slot < MAX_OFFSETS);
         let offset = offset.unwrap_or(u32::MAX);
@@ -228,6 +239,9 @@ impl DrawStream {
         if self.mask & INDEX_BUFFER != 0 {
             self.encode_buffer_slice(self.current.index_buffer);
         }
+        if self.mask & INDEX_TYPE != 0 {
+            self.stream.push(self.current.index_type.as_raw() as u16);
+        }
         for slot in 0..MAX_OFFSETS {
             if self.mask & (DYNAMIC_OFFSET0 << slot) != 0 {
                 self.write_u32(self.current.offsets[slot]);
@@ -274,6 +288,8 @@ impl DrawStream {
         let mut instance_count = 0u32;
         let mut first_instance = 0u32;
         let mut vertex_offset = 0u32;
+        let mut index_buffer = (vk::Buffer::null(), 0u32);
+        let mut index_type = vk::IndexType::UINT16;
         let descriptors = [context.bindless_set, context.buffers_set];
 
         unsafe {
@@ -333,12 +349,20 @@ impl DrawStream {
                 } else {
                     vk::Buffer::null()
                 };
+                index_buffer = (buffer, offset);
+            }
+            if mask & INDEX_TYPE != 0 {
+                index_type = vk::IndexType::from_raw(
+                    stream.read().ok_or(DrawStreamError::EndOfStream)? as _,
+                );
+            }
+            if mask & (INDEX_BUFFER | INDEX_TYPE) != 0 {
                 unsafe {
                     context.device.cmd_bind_index_buffer(
                         cb,
-                        buffer,
-                        offset as _,
-                        vk::IndexType::UINT16,
+                        index_buffer.0,
+                        index_buffer.1 as _,
+                        index_type,
                     )
                 };
             }
@@ -194,7 +194,7 @@ impl Staging {
                     aspect_mask: vk::ImageAspectFlags::COLOR,
                     mip_level: mip,
                     base_array_layer: 0,
-                    layer_count: 1,
+                    layer_count: target.desc().array_elements,
                 })
                 .build();
             let range = vk::ImageSubresourceRange {
@@ -202,7 +202,7 @@ impl Staging {
                 base_mip_level: mip,
                 level_count: 1,
                 base_array_layer: 0,
-                layer_count: 1,
+                layer_count: target.desc().array_elements,
             };
 
             self.upload_images
@@ -222,10 +222,8 @@ impl<'a> RenderDemo<'a> {
             stream.set_dynamic_buffer_offset(0, Some(temp));
             stream.set_bind_group(3, Some(self.draw_bind_group));
             for (bone_idx, mesh_idx) in &model.instances {
-                let mesh = &model.static_meshes[*mesh_idx as usize];
-                stream.set_vertex_buffer(0, Some(mesh.vertices));
-                stream.set_vertex_buffer(1, Some(mesh.attributes));
-                stream.set_index_buffer(Some(mesh.indices));
+                let mesh = &self.model.meshes[*mesh_idx as usize];
+                mesh.bind(&mut stream);
                 for submesh in &mesh.submeshes {
                     stream.set_bind_group(
                         1,